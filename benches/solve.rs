@@ -0,0 +1,177 @@
+//! Benchmarks for solving the 3x3 tree.
+//!
+//! The first pair compares the grid representations. Each one walks the full game tree with a
+//! plain negamax, once through a `Game`, which stores its grid as a pair of bitboards, and once
+//! through `ArrayGame`, a copy of how the grid was stored before that, one cell per element.
+//! `ArrayGame` keeps the same history and checks the same rules as `Game`, so that only the grid
+//! differs between the two.
+//!
+//! The second pair compares the `Solver` with and without alpha-beta pruning.
+
+use criterion::{ criterion_group, criterion_main, Criterion };
+
+use xsos::{ DrawRule, Game, Mark, MoveOutcome, Outcome, PlayError, Position, Variant };
+use xsos::ai::Solver;
+
+/// A 3x3 game that stores its grid as an array of cells and checks for a win by going through
+/// the 8 lines, as `Game` did before bitboards.
+struct ArrayGame {
+    cells: [[Option<Mark>; 3]; 3],
+    last: Option<Mark>,
+    turn: Mark,
+    outcome: Option<Outcome>,
+    history: Vec<(Position, Mark)>,
+    undone: Vec<(Position, Mark)>,
+    draw: DrawRule,
+    variant: Variant
+}
+
+const LINES: [[Position; 3]; 8] = [
+    [(0, 0), (0, 1), (0, 2)], [(1, 0), (1, 1), (1, 2)], [(2, 0), (2, 1), (2, 2)],
+    [(0, 0), (1, 0), (2, 0)], [(0, 1), (1, 1), (2, 1)], [(0, 2), (1, 2), (2, 2)],
+    [(0, 0), (1, 1), (2, 2)], [(0, 2), (1, 1), (2, 0)]
+];
+
+impl ArrayGame {
+    fn start(first: Mark) -> Self {
+        Self {
+            cells: [[None; 3]; 3],
+            last: None,
+            turn: first,
+            outcome: None,
+            history: Vec::new(),
+            undone: Vec::new(),
+            draw: DrawRule::default(),
+            variant: Variant::default()
+        }
+    }
+
+    fn play(&mut self, (r, c): Position) -> Result<MoveOutcome, PlayError> {
+        if self.outcome.is_some() {
+            return Err(PlayError::GameOver);
+        } else if r >= 3 || c >= 3 {
+            return Err(PlayError::OutOfBounds);
+        } else if self.cells[r][c].is_some() {
+            return Err(PlayError::AlreadyMarked);
+        }
+
+        let m = self.turn;
+
+        self.cells[r][c] = Some(m);
+        self.last = Some(m);
+        self.history.push(((r, c), m));
+        self.outcome = self.evaluate();
+
+        if self.outcome.is_none() {
+            self.turn = m.swap();
+        }
+
+        self.undone.clear();
+
+        match self.outcome {
+            Some(outcome) => Ok(MoveOutcome::GameOver(outcome)),
+            None => Ok(MoveOutcome::InPlay)
+        }
+    }
+
+    fn undo(&mut self) -> Option<(Position, Mark)> {
+        let ((r, c), m) = self.history.pop()?;
+
+        self.cells[r][c] = None;
+        self.last = if self.cells.iter().flatten().any(Option::is_some) { Some(m.swap()) } else { None };
+        self.turn = m;
+        self.outcome = None;
+        self.undone.push(((r, c), m));
+
+        Some(((r, c), m))
+    }
+
+    fn evaluate(&self) -> Option<Outcome> {
+        let m = self.last?;
+        let marks = |line: &[Position; 3]| line.map(|(r, c)| self.cells[r][c]);
+
+        if LINES.iter().any(|line| marks(line) == [Some(m); 3]) {
+            match self.variant {
+                Variant::Standard => Some(Outcome::Win(m)),
+                Variant::Misere => Some(Outcome::Win(m.swap()))
+            }
+        } else {
+            let is_draw = match self.draw {
+                DrawRule::FullGrid => self.cells.iter().flatten().all(Option::is_some),
+                DrawRule::Blocked | DrawRule::PerfectPlay => LINES.iter().all(|line| {
+                    marks(line).contains(&Some(Mark::X)) && marks(line).contains(&Some(Mark::O))
+                })
+            };
+
+            if is_draw { Some(Outcome::Draw) } else { None }
+        }
+    }
+}
+
+fn array_negamax(game: &mut ArrayGame) -> i8 {
+    let mut value = -1;
+
+    for p in (0..3).flat_map(|r| (0..3).map(move |c| (r, c))) {
+        if game.cells[p.0][p.1].is_none() {
+            value = value.max(move_value(game.play(p).unwrap(), || -array_negamax(game)));
+            game.undo();
+        }
+    }
+
+    value
+}
+
+fn bitboard_negamax(game: &mut Game) -> i8 {
+    let mut value = -1;
+
+    for p in (0..3).flat_map(|r| (0..3).map(move |c| (r, c))) {
+        if game.grid().is_unmarked_at(p) {
+            value = value.max(move_value(game.play(p).unwrap(), || -bitboard_negamax(game)));
+            game.undo();
+        }
+    }
+
+    value
+}
+
+/// The value of a move for the player who made it, where `rest` values the game after it.
+fn move_value(outcome: MoveOutcome, rest: impl FnOnce() -> i8) -> i8 {
+    match outcome {
+        MoveOutcome::GameOver(Outcome::Win(_)) => 1,
+        MoveOutcome::GameOver(Outcome::Draw) => 0,
+        MoveOutcome::InPlay => rest()
+    }
+}
+
+fn walk_the_tree_on_an_array_grid(c: &mut Criterion) {
+    c.bench_function("walk the full 3x3 tree on an array-backed grid", |b| {
+        b.iter(|| array_negamax(&mut ArrayGame::start(Mark::X)))
+    });
+}
+
+fn walk_the_tree_on_a_bitboard_grid(c: &mut Criterion) {
+    c.bench_function("walk the full 3x3 tree on a bitboard-backed grid", |b| {
+        b.iter(|| bitboard_negamax(&mut Game::start(Mark::X)))
+    });
+}
+
+/// Every position is searched, although transpositions are only searched once.
+fn solve_without_pruning(c: &mut Criterion) {
+    c.bench_function("solve the 3x3 tree without pruning", |b| {
+        b.iter(|| Solver::new().with_pruning(false).solve(&Game::start(Mark::X)))
+    });
+}
+
+fn solve_with_pruning(c: &mut Criterion) {
+    c.bench_function("solve the 3x3 tree with alpha-beta pruning", |b| {
+        b.iter(|| Solver::new().solve(&Game::start(Mark::X)))
+    });
+}
+
+criterion_group! {
+    name = grids;
+    config = Criterion::default().sample_size(10);
+    targets = walk_the_tree_on_an_array_grid, walk_the_tree_on_a_bitboard_grid
+}
+criterion_group!(solvers, solve_without_pruning, solve_with_pruning);
+criterion_main!(grids, solvers);
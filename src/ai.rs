@@ -1,125 +1,985 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::sync::{ Mutex, MutexGuard, OnceLock, PoisonError };
+
 use rand::seq::SliceRandom;
-use rand::thread_rng;
+use rand::Rng;
+
+use crate::bits::CAPACITY;
+use crate::game::Game;
+use crate::grid::{ Grid, Position, Shape };
+use crate::mark::Mark;
+use crate::referee::{ self, Outcome, Rules, Variant };
+
+pub mod notakto;
+pub mod qubic;
+mod table;
+pub mod ultimate;
+pub mod wild;
+
+pub use table::{ Stats, TranspositionTable };
+use table::{ Bound, Entry, Key };
 
-use crate::game::{ Game, unchecked_play };
-use crate::grid::Position;
-use crate::referee::Outcome;
+/// The most cells that a grid can have for the solver to search a game on it to the end in a
+/// reasonable time, which is enough for a 3x4 grid.
+pub const MAX_SOLVABLE_CELLS: usize = 12;
 
-pub fn random_move(game: &Game) -> Position {
-    let mut rng = thread_rng();
-    moves(game).choose(&mut rng).cloned().unwrap()
+/// Returns whether the solver can search a game on a grid of the given `shape` to the end in a
+/// reasonable time, i.e. whether it has at most [`MAX_SOLVABLE_CELLS`] cells.
+///
+/// # Examples
+///
+/// ```
+/// use xsos::Shape;
+/// use xsos::ai;
+///
+/// assert!(ai::can_solve(Shape::new(3, 4, 3)));
+/// assert!(!ai::can_solve(Shape::new(5, 5, 4)));
+/// ```
+///
+/// [`MAX_SOLVABLE_CELLS`]: ./constant.MAX_SOLVABLE_CELLS.html
+pub fn can_solve(shape: Shape) -> bool {
+    shape.ncells() <= MAX_SOLVABLE_CELLS
 }
 
+/// Returns one of the equally-best moves given by [`moves`], chosen using the given `rng`.
+///
+/// [`moves`]: ./fn.moves.html
+pub fn random_move<R: Rng + ?Sized>(game: &Game, rng: &mut R) -> Position {
+    moves(game).choose(rng).cloned().unwrap()
+}
+
+/// Returns the positions of all the equally-best moves that can be played in the given `Game`,
+/// in row-major order.
+///
+/// Moves are scored with [`Scoring::DepthAware`], so a quicker win beats a slower one and a slower
+/// loss beats a quicker one.
+///
+/// The positions searched are remembered for the lifetime of the process, so each one is only
+/// ever searched once.
+///
+/// [`Scoring::DepthAware`]: ./enum.Scoring.html#variant.DepthAware
 pub fn moves(game: &Game) -> Vec<Position> {
-    let positions = game.grid().unmarked_positions().collect::<Vec<_>>();
+    shared_solver().moves(game)
+}
+
+/// Returns the positions of all the moves that are equally-best when only looking `plies` moves ahead,
+/// in row-major order.
+///
+/// It's worked out from the values given by [`evaluate_moves`], with any result that takes more
+/// than `plies` moves to reach valued as a draw. So looking 2 plies ahead finds the moves that win
+/// immediately or, failing that, the ones that stop the opponent from winning on their next move.
+///
+/// # Examples
+///
+/// ```
+/// use xsos::{ Game, Mark };
+/// use xsos::ai;
+///
+/// let mut game = Game::start(Mark::X);
+///
+/// game.play((0, 0)).unwrap();
+/// game.play((1, 1)).unwrap();
+/// game.play((0, 1)).unwrap();
+///
+/// // O can see that it has to block at (0, 2)
+/// assert_eq!(ai::lookahead_moves(&game, 2), vec![(0, 2)]);
+///
+/// // But looking only 1 ply ahead every move seems as good as any other
+/// assert_eq!(ai::lookahead_moves(&game, 1).len(), 6);
+/// ```
+///
+/// [`evaluate_moves`]: ./fn.evaluate_moves.html
+pub fn lookahead_moves(game: &Game, plies: usize) -> Vec<Position> {
+    let horizon_value = |evaluation: Evaluation| match evaluation.value {
+        Value::Win if evaluation.plies <= plies => 1,
+        Value::Loss if evaluation.plies <= plies => -1,
+        _ => 0
+    };
+
+    best_moves(evaluate_moves(game), i8::MIN, |(_, evaluation), _| horizon_value(evaluation))
+        .into_iter()
+        .map(|(pos, _)| pos)
+        .collect()
+}
+
+/// Returns an [`Evaluation`] of every move that can be played in the given `Game`, in row-major order.
+///
+/// # Examples
+///
+/// ```
+/// use xsos::{ Game, Mark };
+/// use xsos::ai::{ self, Evaluation, Value };
+///
+/// let mut game = Game::start(Mark::X);
+///
+/// game.play((0, 0)).unwrap();
+/// game.play((1, 1)).unwrap();
+/// game.play((0, 1)).unwrap();
+///
+/// let evaluations = ai::evaluate_moves(&game);
+///
+/// // O has to block at (0, 2)
+/// assert_eq!(evaluations[0], ((0, 2), Evaluation { value: Value::Draw, plies: 6 }));
+///
+/// // Anything else and X wins with their next move
+/// assert_eq!(evaluations[1], ((1, 0), Evaluation { value: Value::Loss, plies: 2 }));
+/// assert_eq!(evaluations[1].1.to_string(), "loses in 2");
+/// ```
+///
+/// [`Evaluation`]: ./struct.Evaluation.html
+pub fn evaluate_moves(game: &Game) -> Vec<(Position, Evaluation)> {
+    shared_solver().evaluate_moves(game)
+}
+
+/// Returns the moves that are played from the given `Game` until it's over when both players
+/// play the first of their equally-best moves, in the order they're played.
+///
+/// # Examples
+///
+/// ```
+/// use xsos::{ Game, Mark };
+/// use xsos::ai;
+///
+/// let game = "xo./xo./... o".parse::<Game>().unwrap();
+///
+/// // O wins straight away
+/// assert_eq!(ai::principal_variation(&game), vec![(2, 1)]);
+/// ```
+pub fn principal_variation(game: &Game) -> Vec<Position> {
+    shared_solver().principal_variation(game)
+}
+
+/// Returns the process-wide `Solver` that's used by [`moves`], [`evaluate_moves`] and
+/// [`principal_variation`].
+///
+/// It stays locked until the returned guard is dropped, so calling any of those functions on the
+/// same thread while holding it deadlocks. Playing a [`Game`] doesn't use it, not even under
+/// `DrawRule::PerfectPlay`.
+///
+/// [`Game`]: ../struct.Game.html
+/// [`moves`]: ./fn.moves.html
+/// [`evaluate_moves`]: ./fn.evaluate_moves.html
+/// [`principal_variation`]: ./fn.principal_variation.html
+pub(crate) fn shared_solver() -> MutexGuard<'static, Solver> {
+    static SOLVER: OnceLock<Mutex<Solver>> = OnceLock::new();
+
+    SOLVER
+        .get_or_init(|| Mutex::new(Solver::new()))
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+}
 
-    match positions.len() {
-        0 | 1 | 9 => positions,
-        _ => find_best_moves(&mut game.clone())
+/// Returns `true` if the given `Game` is drawn with perfect play from both players.
+///
+/// It's used to decide `DrawRule::PerfectPlay` draws after every move. The search uses a `Solver`
+/// that belongs to the current thread, so that playing never waits on, or deadlocks with, the
+/// [`shared_solver`].
+///
+/// [`shared_solver`]: ./fn.shared_solver.html
+pub(crate) fn is_drawn_with_perfect_play(game: &Game) -> bool {
+    thread_local! {
+        static SOLVER: RefCell<Solver> = RefCell::new(Solver::new());
     }
+
+    SOLVER.with(|solver| solver.borrow_mut().solve(game) == Value::Draw)
 }
 
-fn find_best_moves(game: &mut Game) -> Vec<Position> {
-    if let None = game.outcome() {
-        let mut value = i8::MIN;
-        let mut positions = Vec::new();
+/// The game-theoretic value of a position for the player whose turn it is, i.e. the
+/// result they can force with perfect play.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+pub enum Value {
+    Loss,
+    Draw,
+    Win
+}
 
-        for pos in game.grid().unmarked_positions() {
-            let mut next_game = game.clone();
+/// The result of a move, for the player who made it, and how many plies it takes
+/// to get there with perfect play.
+///
+/// The plies count the move itself, so a move that wins the game on the spot
+/// wins in 1.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Evaluation {
+    /// The result the player who made the move can force.
+    pub value: Value,
 
-            unchecked_play(&mut next_game, pos);
+    /// The number of plies until the game is over.
+    pub plies: usize
+}
 
-            let next_value = negamax(&mut next_game, -1);
+impl fmt::Display for Evaluation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.value {
+            Value::Win => write!(f, "wins in {}", self.plies),
+            Value::Draw => write!(f, "draws in {}", self.plies),
+            Value::Loss => write!(f, "loses in {}", self.plies)
+        }
+    }
+}
 
-            if next_value > value {
-                value = next_value;
-                positions.clear();
-                positions.push(pos);
-            } else if next_value == value {
-                positions.push(pos);
-            }
+/// How the [`Solver`] scores the positions it searches.
+///
+/// The scoring never changes the [`Value`] of a position, only which of the moves that lead
+/// to that value count as the best ones.
+///
+/// # Examples
+///
+/// ```
+/// use xsos::{ Game, Mark };
+/// use xsos::ai::{ Scoring, Solver };
+///
+/// let mut game = Game::start(Mark::X);
+///
+/// game.play((0, 0)).unwrap();
+/// game.play((0, 2)).unwrap();
+/// game.play((1, 0)).unwrap();
+/// game.play((2, 1)).unwrap();
+///
+/// // X wins on the spot at (2, 0)
+/// assert_eq!(Solver::new().moves(&game), vec![(2, 0)]);
+///
+/// // But every one of these wins eventually
+/// assert_eq!(
+///     Solver::new().with_scoring(Scoring::Plain).moves(&game),
+///     vec![(1, 1), (1, 2), (2, 0), (2, 2)]
+/// );
+/// ```
+///
+/// [`Solver`]: ./struct.Solver.html
+/// [`Value`]: ./enum.Value.html
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum Scoring {
+    /// A win is a win, and a loss is a loss, no matter how long it takes.
+    Plain,
+
+    /// Quicker wins and slower losses score better.
+    #[default]
+    DepthAware
+}
+
+/// The order in which the [`Solver`] searches the moves available in a position.
+///
+/// The order never changes the result of a search, only how quickly alpha-beta
+/// pruning can cut off the moves that don't need to be looked at.
+///
+/// [`Solver`]: ./struct.Solver.html
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum MoveOrdering {
+    /// Search the moves in row-major order.
+    RowMajor,
+
+    /// Search the moves closest to the centre first and, among those that are equally close,
+    /// the ones in the corners first.
+    CentreFirst,
+
+    /// Search the moves that win first, then the moves that block a win and then
+    /// the rest as for `CentreFirst`.
+    #[default]
+    ThreatsFirst
+}
+
+/// A game tree search that uses negamax with alpha-beta pruning and a [`TranspositionTable`].
+///
+/// # Examples
+///
+/// ```
+/// use xsos::{ Game, Mark };
+/// use xsos::ai::{ MoveOrdering, Solver };
+///
+/// let mut game = Game::start(Mark::X);
+///
+/// game.play((0, 0)).unwrap();
+/// game.play((0, 2)).unwrap();
+/// game.play((1, 1)).unwrap();
+///
+/// let mut solver = Solver::new().with_ordering(MoveOrdering::RowMajor);
+///
+/// // O has to block at (2, 2)
+/// assert_eq!(solver.moves(&game), vec![(2, 2)]);
+/// ```
+///
+/// [`TranspositionTable`]: ./struct.TranspositionTable.html
+#[derive(Debug, Clone)]
+pub struct Solver {
+    ordering: MoveOrdering,
+    pruning: bool,
+    scoring: Scoring,
+    table: TranspositionTable
+}
+
+impl Solver {
+    /// Creates a new `Solver` that uses the default [`MoveOrdering`] and an empty [`TranspositionTable`].
+    ///
+    /// [`MoveOrdering`]: ./enum.MoveOrdering.html
+    /// [`TranspositionTable`]: ./struct.TranspositionTable.html
+    pub fn new() -> Self {
+        Self {
+            ordering: MoveOrdering::default(),
+            pruning: true,
+            scoring: Scoring::default(),
+            table: TranspositionTable::new()
+        }
+    }
+
+    /// Sets the [`MoveOrdering`] used by this `Solver`.
+    ///
+    /// [`MoveOrdering`]: ./enum.MoveOrdering.html
+    pub fn with_ordering(mut self, ordering: MoveOrdering) -> Self {
+        self.ordering = ordering;
+        self
+    }
+
+    /// Turns alpha-beta pruning on or off. It's on by default.
+    ///
+    /// Without pruning every position in the game tree gets searched, which is only
+    /// useful for measuring the size of the tree.
+    pub fn with_pruning(mut self, pruning: bool) -> Self {
+        self.pruning = pruning;
+        self
+    }
+
+    /// Sets the [`Scoring`] used by this `Solver`.
+    ///
+    /// [`Scoring`]: ./enum.Scoring.html
+    pub fn with_scoring(mut self, scoring: Scoring) -> Self {
+        self.scoring = scoring;
+        self
+    }
+
+    /// Returns the [`TranspositionTable`] used by this `Solver`.
+    ///
+    /// [`TranspositionTable`]: ./struct.TranspositionTable.html
+    pub fn table(&self) -> &TranspositionTable {
+        &self.table
+    }
+
+    /// Returns the [`Value`] of the given `Game` for the player whose turn it is.
+    ///
+    /// Once the game is over that's the player who made the final move.
+    ///
+    /// [`Value`]: ./enum.Value.html
+    pub fn solve(&mut self, game: &Game) -> Value {
+        let value = match game.outcome() {
+            None => self.negamax(&mut game.grid().clone(), game.turn(), game.rules(), -INFINITY, INFINITY),
+            Some(outcome) => score(outcome, game.turn())
+        };
+
+        to_value(value)
+    }
+
+    /// Returns an [`Evaluation`] of every move that can be played in the given `Game`, in row-major order.
+    ///
+    /// [`Evaluation`]: ./struct.Evaluation.html
+    pub fn evaluate_moves(&mut self, game: &Game) -> Vec<(Position, Evaluation)> {
+        if game.outcome().is_some() {
+            return Vec::new();
+        }
+
+        let mut grid = game.grid().clone();
+        let turn = game.turn();
+        let rules = game.rules();
+        let last = grid.last_mark();
+
+        game.grid()
+            .unmarked_positions()
+            .map(|pos| {
+                grid.mark(pos, turn);
+
+                let value = -self.negamax(&mut grid, turn.swap(), rules, -INFINITY, INFINITY);
+                let value = self.decay(value);
+                let plies = match (self.scoring, to_value(value)) {
+                    (Scoring::DepthAware, Value::Win | Value::Loss) => (WIN - value.abs()) as usize,
+                    _ => 1 + self.find_principal_variation(&mut grid, turn.swap(), rules).len()
+                };
+
+                grid.unmark(pos, last);
+
+                (pos, Evaluation { value: to_value(value), plies })
+            })
+            .collect()
+    }
+
+    /// Returns the moves that are played from the given `Game` until it's over when both players
+    /// play the first of their equally-best moves.
+    pub fn principal_variation(&mut self, game: &Game) -> Vec<Position> {
+        if game.outcome().is_some() {
+            return Vec::new();
+        }
+
+        self.find_principal_variation(&mut game.grid().clone(), game.turn(), game.rules())
+    }
+
+    /// Returns the positions of all the equally-best moves that can be played in the given `Game`,
+    /// in row-major order.
+    pub fn moves(&mut self, game: &Game) -> Vec<Position> {
+        let positions = game.grid().unmarked_positions().collect::<Vec<_>>();
+
+        match positions.len() {
+            0 | 1 => positions,
+            // Every opening move on the 3x3 grid leads to a draw, unlike in misère
+            9 if game.grid().shape() == Shape::default() && game.variant() == Variant::Standard => positions,
+            _ => self.find_best_moves(game)
+        }
+    }
+
+    fn find_best_moves(&mut self, game: &Game) -> Vec<Position> {
+        if game.outcome().is_some() {
+            return Vec::new();
         }
 
+        let mut grid = game.grid().clone();
+        let turn = game.turn();
+        let rules = game.rules();
+        let last = grid.last_mark();
+
+        let moves = self.order(&mut grid, turn);
+        let mut positions = best_moves(moves, -INFINITY, |pos, best| {
+            grid.mark(pos, turn);
+
+            let beta = -(self.undecay(best) - 1);
+            let value = -self.negamax(&mut grid, turn.swap(), rules, -INFINITY, beta);
+
+            grid.unmark(pos, last);
+
+            self.decay(value)
+        });
+
+        positions.sort_unstable();
         positions
-    } else {
-        Vec::new()
     }
-}
 
-fn negamax(game: &mut Game, color: i8) -> i8 {
-    match game.outcome() {
-        None => {
-            let mut value = i8::MIN;
+    /// Returns the value of the `grid` for the player with the given `turn`.
+    ///
+    /// The value is only exact if it lies strictly within the window `(alpha, beta)`. Otherwise,
+    /// it's an upper bound if it's at most `alpha` and a lower bound if it's at least `beta`.
+    fn negamax(&mut self, grid: &mut Grid, turn: Mark, rules: Rules, alpha: i16, beta: i16) -> i16 {
+        let key = Key::new(grid, turn, rules);
 
-            for pos in game.grid().unmarked_positions() {
-                let mut next_game = game.clone();
+        if let Some(value) = self.table.probe(&key, alpha, beta) {
+            return value;
+        }
 
-                unchecked_play(&mut next_game, pos);
+        let original = (alpha, beta);
+        let value = match referee::evaluate(grid, rules) {
+            None => {
+                // The best move gets decayed once it's found, so the window has to be widened to match
+                let (mut alpha, beta) = (self.undecay(alpha), self.undecay(beta));
+                let last = grid.last_mark();
+                let mut value = -INFINITY;
+
+                for pos in self.order(grid, turn) {
+                    grid.mark(pos, turn);
+                    value = value.max(-self.negamax(grid, turn.swap(), rules, -beta, -alpha));
+                    grid.unmark(pos, last);
+
+                    alpha = alpha.max(value);
+
+                    if self.pruning && alpha >= beta {
+                        break;
+                    }
+                }
+
+                self.decay(value)
+            },
+            Some(outcome) => score(outcome, turn)
+        };
+
+        let (alpha, beta) = original;
+        let bound = if !self.pruning {
+            Bound::Exact
+        } else if value <= alpha {
+            Bound::Upper
+        } else if value >= beta {
+            Bound::Lower
+        } else {
+            Bound::Exact
+        };
+
+        self.table.store(key, Entry { value, bound });
+
+        value
+    }
+
+    /// Returns the moves that follow on from the `grid` when both players play the first
+    /// of their equally-best moves.
+    fn find_principal_variation(&mut self, grid: &mut Grid, turn: Mark, rules: Rules) -> Vec<Position> {
+        let mut grid = grid.clone();
+        let mut turn = turn;
+        let mut variation = Vec::new();
+
+        while referee::evaluate(&grid, rules).is_none() {
+            let last = grid.last_mark();
+            let mut best = None;
+
+            for pos in grid.unmarked_positions().collect::<Vec<_>>() {
+                grid.mark(pos, turn);
+                let value = -self.negamax(&mut grid, turn.swap(), rules, -INFINITY, INFINITY);
+                let value = self.decay(value);
+                grid.unmark(pos, last);
+
+                if best.is_none_or(|(_, best_value)| value > best_value) {
+                    best = Some((pos, value));
+                }
+            }
+
+            let (pos, _) = best.unwrap();
+
+            grid.mark(pos, turn);
+            turn = turn.swap();
+            variation.push(pos);
+        }
+
+        variation
+    }
+
+    /// Moves a score one ply further away from the end of the game, if scores depend on depth.
+    fn decay(&self, value: i16) -> i16 {
+        match self.scoring {
+            Scoring::Plain => value,
+            Scoring::DepthAware => value - value.signum()
+        }
+    }
+
+    /// Undoes `decay`, for the bounds of a search window.
+    fn undecay(&self, value: i16) -> i16 {
+        match self.scoring {
+            Scoring::Plain => value,
+            Scoring::DepthAware => value + value.signum()
+        }
+    }
+
+    fn order(&self, grid: &mut Grid, turn: Mark) -> Vec<Position> {
+        let mut positions = grid.unmarked_positions().collect::<Vec<_>>();
+
+        match self.ordering {
+            MoveOrdering::RowMajor => (),
+            MoveOrdering::CentreFirst => {
+                let shape = grid.shape();
+
+                positions.sort_by_key(|&pos| positional_rank(shape, pos));
+            },
+            MoveOrdering::ThreatsFirst => {
+                let shape = grid.shape();
+                let last = grid.last_mark();
+                let mut threat_rank = |pos: Position, mark: Mark| {
+                    grid.mark(pos, mark);
+                    let is_win = referee::is_win(grid);
+                    grid.unmark(pos, last);
+                    is_win
+                };
+
+                let mut ranked = positions
+                    .iter()
+                    .map(|&pos| {
+                        let rank = if threat_rank(pos, turn) {
+                            0
+                        } else if threat_rank(pos, turn.swap()) {
+                            1
+                        } else {
+                            2
+                        };
 
-                value = std::cmp::max(value, color * negamax(&mut next_game, -color));
+                        ((rank, positional_rank(shape, pos)), pos)
+                    })
+                    .collect::<Vec<_>>();
+
+                ranked.sort_by_key(|&(rank, _)| rank);
+                positions = ranked.into_iter().map(|(_, pos)| pos).collect();
             }
+        }
+
+        positions
+    }
+}
 
-            color * value
-        },
-        Some(outcome) => -(color * score(outcome))
+impl Default for Solver {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
-fn score(outcome: Outcome) -> i8 {
+/// The score of a win on the spot. It has to be larger than the number of plies in any game
+/// so that depth-aware scores never reach 0.
+const WIN: i16 = CAPACITY as i16 + 1;
+
+const INFINITY: i16 = WIN + 1;
+
+/// The value of a finished game for the given player.
+fn score(outcome: Outcome, mark: Mark) -> i16 {
     match outcome {
-        Outcome::Win => 2,
-        Outcome::Draw => 1
+        Outcome::Win(winner) if winner == mark => WIN,
+        Outcome::Win(_) => -WIN,
+        Outcome::Draw => 0
     }
 }
 
+fn to_value(score: i16) -> Value {
+    match score {
+        0 => Value::Draw,
+        s if s > 0 => Value::Win,
+        _ => Value::Loss
+    }
+}
+
+/// Returns the equally-best of the given `moves`, in the same order, where `value` gives the value
+/// of a move for the player who makes it.
+///
+/// `value` is also given the value of the best move so far, starting at `lowest`. Only the moves
+/// that are at least as good as that one need an exact value, so anything worse is allowed to fail
+/// low, i.e. its search can stop as soon as it's sure to come out below the best value so far.
+pub(crate) fn best_moves<M: Copy, V: Copy + Ord>(moves: impl IntoIterator<Item = M>, lowest: V, mut value: impl FnMut(M, V) -> V) -> Vec<M> {
+    let mut best_value = lowest;
+    let mut best = Vec::new();
+
+    for mv in moves {
+        let next_value = value(mv, best_value);
+
+        if next_value > best_value {
+            best_value = next_value;
+            best.clear();
+        }
+
+        if next_value == best_value {
+            best.push(mv);
+        }
+    }
+
+    best
+}
+
+/// Ranks a position by how close it is to the centre and then by how close it is to a corner.
+///
+/// Lower ranks are searched first.
+fn positional_rank(shape: Shape, (r, c): Position) -> (usize, isize) {
+    // Work with doubled coordinates so that the centre of an even sided grid is a whole number
+    let dr = (2 * r as isize - (shape.rows() as isize - 1)).abs();
+    let dc = (2 * c as isize - (shape.columns() as isize - 1)).abs();
+
+    (dr.max(dc) as usize, -(dr + dc))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::mark::Mark;
+    use std::collections::HashSet;
 
     #[test]
-    fn it_finds_the_blocking_move_to_avoid_losing() {
-        let mut game = Game::start(Mark::X);
+    fn the_equally_best_moves_are_collected_in_order() {
+        let values = [1, 3, 2, 3];
+        let mut bests = Vec::new();
+        let best = best_moves(0..values.len(), i32::MIN, |i, best| {
+            bests.push(best);
+            values[i]
+        });
+
+        assert_eq!(best, vec![1, 3]);
+        assert_eq!(bests, vec![i32::MIN, 1, 3, 3]);
+    }
 
-        game.play((0, 0));
-        game.play((0, 2));
-        game.play((1, 1));
+    #[test]
+    fn it_finds_the_blocking_move_to_avoid_losing() {
+        let game = "x.o/.x./... o".parse::<Game>().unwrap();
 
         assert_eq!(moves(&game), vec![(2, 2)]);
     }
 
     #[test]
     fn it_has_no_good_moves_since_every_position_is_losing() {
-        let mut game = Game::start(Mark::X);
+        let game = "xo./.x./... o".parse::<Game>().unwrap();
 
-        game.play((0, 0));
-        game.play((0, 1));
-        game.play((1, 1));
+        assert_eq!(
+            Solver::new().with_scoring(Scoring::Plain).moves(&game),
+            vec![(0, 2), (1, 0), (1, 2), (2, 0), (2, 1), (2, 2)]
+        );
 
-        assert_eq!(moves(&game), vec![(0, 2), (1, 0), (1, 2), (2, 0), (2, 1), (2, 2)]);
+        // Blocking at (2, 2) holds out the longest
+        assert_eq!(moves(&game), vec![(2, 2)]);
     }
 
     #[test]
     fn it_finds_the_winning_moves() {
-        let mut game = Game::start(Mark::X);
+        let game = "x.o/x../.o. x".parse::<Game>().unwrap();
+
+        assert_eq!(
+            Solver::new().with_scoring(Scoring::Plain).moves(&game),
+            vec![(1, 1), (1, 2), (2, 0), (2, 2)]
+        );
+
+        // Winning on the spot at (2, 0) is the quickest
+        assert_eq!(moves(&game), vec![(2, 0)]);
+    }
 
-        game.play((0, 0));
-        game.play((0, 2));
-        game.play((1, 0));
-        game.play((2, 1));
+    #[test]
+    fn it_plays_misere_to_avoid_completing_a_line() {
+        let game = Game::start(Mark::X).with_variant(Variant::Misere);
+
+        // Only the centre doesn't lose, since X can then mirror every move that O makes
+        assert_eq!(moves(&game), vec![(1, 1)]);
+        assert_eq!(Solver::new().solve(&game), Value::Draw);
 
-        assert_eq!(moves(&game), vec![(1, 1), (1, 2), (2, 0), (2, 2)]);
+        // Completing the middle row loses on the spot
+        let game = "xx./oo./x.. o".parse::<Game>().unwrap().with_variant(Variant::Misere);
+        let evaluations = evaluate_moves(&game);
+
+        assert!(!moves(&game).contains(&(1, 2)));
+        assert!(evaluations.contains(&((1, 2), Evaluation { value: Value::Loss, plies: 1 })));
+    }
+
+    #[test]
+    fn it_finds_the_quickest_of_the_forced_wins() {
+        let game = "xo./.x./..o x".parse::<Game>().unwrap();
+
+        // Both forks win in 3 whereas (1, 2) and (2, 1) take 5 plies
+        assert_eq!(moves(&game), vec![(1, 0), (2, 0)]);
     }
 
     #[test]
     fn it_favors_winning_over_blocking() {
+        let game = "x.o/.../x.o x".parse::<Game>().unwrap();
+
+        assert_eq!(moves(&game), vec![(1, 0)]);
+    }
+
+    #[test]
+    fn centre_first_ordering() {
+        let shape = Shape::default();
+        let mut positions = Grid::new().unmarked_positions().collect::<Vec<_>>();
+
+        positions.sort_by_key(|&pos| positional_rank(shape, pos));
+
+        assert_eq!(positions, vec![
+            (1, 1),
+            (0, 0), (0, 2), (2, 0), (2, 2),
+            (0, 1), (1, 0), (1, 2), (2, 1)
+        ]);
+    }
+
+    #[test]
+    fn threats_first_ordering() {
         let mut game = Game::start(Mark::X);
 
-        game.play((2, 0));
-        game.play((0, 2));
-        game.play((0, 0));
-        game.play((2, 2));
+        game.play((0, 0)).unwrap();
+        game.play((2, 2)).unwrap();
+        game.play((0, 1)).unwrap();
+        game.play((2, 1)).unwrap();
 
-        assert_eq!(moves(&game), vec![(1, 0)]);
+        let mut grid = game.grid().clone();
+        let solver = Solver::new().with_ordering(MoveOrdering::ThreatsFirst);
+
+        // X wins at (0, 2) and blocks at (2, 0), then the centre and remaining corner
+        assert_eq!(solver.order(&mut grid, Mark::X), vec![
+            (0, 2), (2, 0), (1, 1), (1, 0), (1, 2)
+        ]);
+    }
+
+    #[test]
+    fn the_3x3_game_is_a_draw() {
+        let mut solver = Solver::new();
+
+        assert_eq!(solver.solve(&Game::start(Mark::X)), Value::Draw);
+        assert_eq!(solver.solve(&Game::start(Mark::O)), Value::Draw);
+    }
+
+    #[test]
+    fn the_full_3x3_tree_has_765_essentially_different_positions() {
+        let mut solver = Solver::new().with_pruning(false);
+
+        solver.solve(&Game::start(Mark::X));
+
+        assert_eq!(solver.table().len(), 765);
+        assert_eq!(solver.table().stats().misses, 765);
+        assert!(solver.table().stats().hits > 0);
+
+        // Solving it again is just one lookup
+        solver.solve(&Game::start(Mark::X));
+
+        assert_eq!(solver.table().stats().misses, 765);
+    }
+
+    #[test]
+    fn symmetric_positions_are_only_searched_once() {
+        let mut solver = Solver::new();
+        let mut game = Game::start(Mark::X);
+
+        game.play((0, 0)).unwrap();
+        solver.solve(&game);
+
+        let stats = solver.table().stats();
+        let mut game = Game::start(Mark::X);
+
+        game.play((2, 2)).unwrap();
+
+        assert_eq!(solver.solve(&game), Value::Draw);
+        assert_eq!(solver.table().stats(), Stats { hits: stats.hits + 1, misses: stats.misses });
+    }
+
+    #[test]
+    fn it_agrees_with_the_unpruned_search_when_the_table_is_shared() {
+        let mut solver = Solver::new().with_scoring(Scoring::Plain);
+
+        for game in reachable_games() {
+            assert_eq!(solver.moves(&game), unpruned::moves(&game));
+        }
+    }
+
+    #[test]
+    fn it_agrees_with_the_unpruned_search_on_every_reachable_position() {
+        let orderings = [MoveOrdering::RowMajor, MoveOrdering::CentreFirst, MoveOrdering::ThreatsFirst];
+
+        for game in reachable_games() {
+            let expected = unpruned::moves(&game);
+
+            for &ordering in &orderings {
+                let mut solver = Solver::new().with_ordering(ordering).with_scoring(Scoring::Plain);
+
+                assert_eq!(solver.moves(&game), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn it_evaluates_every_move() {
+        let mut game = Game::start(Mark::X);
+
+        game.play((0, 0)).unwrap();
+        game.play((0, 1)).unwrap();
+        game.play((1, 1)).unwrap();
+        game.play((2, 2)).unwrap();
+
+        let evaluations = evaluate_moves(&game);
+        let evaluation = |pos: Position| evaluations.iter().find(|&&(p, _)| p == pos).unwrap().1;
+
+        assert_eq!(evaluations.len(), 5);
+
+        // X wins straight away with a fork...
+        assert_eq!(evaluation((1, 0)), Evaluation { value: Value::Win, plies: 3 });
+        assert_eq!(evaluation((2, 0)), Evaluation { value: Value::Win, plies: 3 });
+
+        // ...but otherwise O blocks and it's a draw once the grid is full
+        assert_eq!(evaluation((0, 2)), Evaluation { value: Value::Draw, plies: 5 });
+        assert_eq!(evaluation((0, 2)).to_string(), "draws in 5");
+    }
+
+    #[test]
+    fn depth_aware_scoring_agrees_with_plain_scoring() {
+        let mut plain = Solver::new().with_scoring(Scoring::Plain);
+        let mut depth_aware = Solver::new();
+
+        for game in reachable_games() {
+            assert_eq!(depth_aware.solve(&game), plain.solve(&game));
+
+            // The depth only ever breaks ties between equally-best moves
+            let best = plain.moves(&game);
+
+            assert!(depth_aware.moves(&game).iter().all(|pos| best.contains(pos)));
+
+            for (pos, evaluation) in depth_aware.evaluate_moves(&game) {
+                let mut next_game = game.clone();
+
+                next_game.play(pos).unwrap();
+
+                // Once a move is played the game gets solved from the opponent's point of view
+                let value = match (next_game.outcome(), plain.solve(&next_game)) {
+                    (Some(_), value) => value,
+                    (None, Value::Win) => Value::Loss,
+                    (None, Value::Draw) => Value::Draw,
+                    (None, Value::Loss) => Value::Win
+                };
+
+                assert_eq!(evaluation.value, value);
+                assert!(evaluation.plies <= game.grid().unmarked_positions().count());
+            }
+        }
+    }
+
+    fn reachable_games() -> Vec<Game> {
+        fn visit(game: Game, seen: &mut HashSet<(Vec<Option<Mark>>, Mark)>, games: &mut Vec<Game>) {
+            let key = (game.grid().cells().cloned().collect(), game.turn());
+
+            if game.is_playing() && seen.insert(key) {
+                for pos in game.grid().unmarked_positions() {
+                    let mut next_game = game.clone();
+
+                    next_game.play(pos).unwrap();
+                    visit(next_game, seen, games);
+                }
+
+                games.push(game);
+            }
+        }
+
+        let mut seen = HashSet::new();
+        let mut games = Vec::new();
+
+        visit(Game::start(Mark::X), &mut seen, &mut games);
+        visit(Game::start(Mark::O), &mut seen, &mut games);
+
+        games
+    }
+
+    /// The original full-width negamax search, kept as a reference.
+    mod unpruned {
+        use crate::game::{ Game, unchecked_play };
+        use crate::grid::Position;
+        use crate::referee::Outcome;
+
+        pub fn moves(game: &Game) -> Vec<Position> {
+            let positions = game.grid().unmarked_positions().collect::<Vec<_>>();
+
+            match positions.len() {
+                0 | 1 | 9 => positions,
+                _ => find_best_moves(&mut game.clone())
+            }
+        }
+
+        fn find_best_moves(game: &mut Game) -> Vec<Position> {
+            let mut value = i8::MIN;
+            let mut positions = Vec::new();
+
+            for pos in game.grid().unmarked_positions() {
+                let mut next_game = game.clone();
+
+                unchecked_play(&mut next_game, pos);
+
+                let next_value = negamax(&mut next_game, -1);
+
+                if next_value > value {
+                    value = next_value;
+                    positions.clear();
+                    positions.push(pos);
+                } else if next_value == value {
+                    positions.push(pos);
+                }
+            }
+
+            positions
+        }
+
+        fn negamax(game: &mut Game, color: i8) -> i8 {
+            match game.outcome() {
+                None => {
+                    let mut value = i8::MIN;
+
+                    for pos in game.grid().unmarked_positions() {
+                        let mut next_game = game.clone();
+
+                        unchecked_play(&mut next_game, pos);
+
+                        value = std::cmp::max(value, color * negamax(&mut next_game, -color));
+                    }
+
+                    color * value
+                },
+                Some(outcome) => -(color * score(outcome))
+            }
+        }
+
+        fn score(outcome: Outcome) -> i8 {
+            match outcome {
+                Outcome::Win(_) => 2,
+                Outcome::Draw => 1
+            }
+        }
     }
 }
@@ -0,0 +1,243 @@
+use std::collections::HashMap;
+use std::sync::{ Mutex, OnceLock, PoisonError };
+
+use crate::ai::best_moves;
+use crate::grid::Grid;
+use crate::mark::Mark;
+use crate::notakto::{ NotaktoGame, NotaktoMove };
+use crate::referee;
+
+/// Returns all the moves that are equally-best when looking `depth` moves ahead in the given
+/// `NotaktoGame`, in the order of [`NotaktoGame::legal_moves`].
+///
+/// Positions that are still in play after `depth` moves are valued as unknown, which is better
+/// than a loss and worse than a win. Passing `usize::MAX` searches every position to the end, so
+/// the moves it returns are the winning ones, if there are any. Otherwise every move loses against
+/// perfect play, and it returns the ones that don't kill the last board, if there are any.
+///
+/// The search is exhaustive, although the positions it finds the value of are remembered for the
+/// lifetime of the process, and boards that are the same up to a rotation or a reflection are
+/// treated as the same board. It's quick for up to 4 boards.
+///
+/// # Examples
+///
+/// ```
+/// use xsos::{ Mark, NotaktoGame };
+/// use xsos::ai::notakto;
+///
+/// let game = NotaktoGame::start(Mark::X);
+///
+/// // On a single board the first player wins by playing in the middle
+/// assert_eq!(notakto::moves(&game, usize::MAX), vec![(0, (1, 1))]);
+///
+/// // But looking 2 moves ahead every move seems as good as any other
+/// assert_eq!(notakto::moves(&game, 2).len(), 9);
+/// ```
+///
+/// [`NotaktoGame::legal_moves`]: ../../struct.NotaktoGame.html#method.legal_moves
+pub fn moves(game: &NotaktoGame, depth: usize) -> Vec<NotaktoMove> {
+    let mut known = known_values().lock().unwrap_or_else(PoisonError::into_inner);
+    let boards = game.boards().iter().map(to_board).collect::<Vec<_>>();
+
+    // Positions are valued exactly and remembered, so there's no window to narrow
+    best_moves(game.legal_moves(), i32::MIN, |(i, p), _| {
+        let mut next = boards.clone();
+
+        next[i] |= 1 << (p.0 * 3 + p.1);
+
+        let next = normalize(&next);
+
+        // Killing the last board loses on the spot, which is worse than a loss that the other
+        // player still has to find
+        if next.is_empty() {
+            -2 * WIN
+        } else {
+            -evaluate(&next, depth.saturating_sub(1), &mut known)
+        }
+    })
+}
+
+/// The value of a position that's won for the player to move.
+const WIN: i32 = 1;
+
+/// A board, where bit `3 * r + c` is set if the cell at `(r, c)` is marked.
+type Board = u16;
+
+/// Returns the value of the position given by the `boards` that are still alive, for the player
+/// to move, looking `depth` moves ahead. It's `WIN`, `-WIN` or `0` if it's unknown.
+///
+/// The values of the positions that are searched to the end are remembered in `known`.
+fn evaluate(boards: &[Board], depth: usize, known: &mut HashMap<Vec<Board>, i32>) -> i32 {
+    // The other player killed the last board
+    if boards.is_empty() {
+        return WIN;
+    }
+
+    // The search reaches the end of the game when it looks as many moves ahead as there are
+    // unmarked cells, and only then is the value known for sure
+    let is_exhaustive = depth >= boards.iter().map(|board| 9 - board.count_ones() as usize).sum();
+
+    if let Some(&value) = known.get(boards).filter(|_| is_exhaustive) {
+        return value;
+    }
+
+    if depth == 0 {
+        return 0;
+    }
+
+    let mut value = -WIN;
+
+    'search: for (i, &board) in boards.iter().enumerate() {
+        // A board that's the same as the one before it has the same moves
+        if i > 0 && boards[i - 1] == board {
+            continue;
+        }
+
+        for cell in (0..9).filter(|cell| board & 1 << cell == 0) {
+            let mut next = boards.to_vec();
+
+            next[i] |= 1 << cell;
+            value = value.max(-evaluate(&normalize(&next), depth - 1, known));
+
+            if value == WIN {
+                break 'search;
+            }
+        }
+    }
+
+    if is_exhaustive {
+        known.insert(boards.to_vec(), value);
+    }
+
+    value
+}
+
+/// Returns the boards that are still alive, each replaced by its smallest rotation or
+/// reflection, and sorted, so that positions which play the same have the same boards.
+fn normalize(boards: &[Board]) -> Vec<Board> {
+    let table = table();
+    let mut alive = boards
+        .iter()
+        .filter(|&&board| !table.dead[board as usize])
+        .map(|&board| table.canonical[board as usize])
+        .collect::<Vec<_>>();
+
+    alive.sort_unstable();
+    alive
+}
+
+fn to_board(grid: &Grid) -> Board {
+    (0..9).filter(|&i| grid.is_marked_at((i / 3, i % 3))).fold(0, |board, i| board | 1 << i)
+}
+
+/// What's known about each of the 512 ways to mark a board.
+struct Table {
+    dead: Vec<bool>,
+    canonical: Vec<Board>
+}
+
+/// The cells that each cell moves to under the 8 rotations and reflections of a board.
+const SYMMETRIES: [[usize; 9]; 8] = [
+    [0, 1, 2, 3, 4, 5, 6, 7, 8],
+    [2, 5, 8, 1, 4, 7, 0, 3, 6],
+    [8, 7, 6, 5, 4, 3, 2, 1, 0],
+    [6, 3, 0, 7, 4, 1, 8, 5, 2],
+    [2, 1, 0, 5, 4, 3, 8, 7, 6],
+    [6, 7, 8, 3, 4, 5, 0, 1, 2],
+    [0, 3, 6, 1, 4, 7, 2, 5, 8],
+    [8, 5, 2, 7, 4, 1, 6, 3, 0]
+];
+
+fn table() -> &'static Table {
+    static TABLE: OnceLock<Table> = OnceLock::new();
+
+    TABLE.get_or_init(|| {
+        let boards = 0..1 << 9;
+        let dead = boards
+            .clone()
+            .map(|board: Board| {
+                let mut grid = Grid::new();
+
+                for i in (0..9).filter(|i| board & 1 << i != 0) {
+                    grid.mark((i / 3, i % 3), Mark::X);
+                }
+
+                referee::is_win(&grid)
+            })
+            .collect();
+        let canonical = boards
+            .map(|board: Board| {
+                SYMMETRIES
+                    .iter()
+                    .map(|to| (0..9).filter(|&i| board & 1 << i != 0).fold(0, |image, i| image | 1 << to[i]))
+                    .min()
+                    .unwrap()
+            })
+            .collect();
+
+        Table { dead, canonical }
+    })
+}
+
+fn known_values() -> &'static Mutex<HashMap<Vec<Board>, i32>> {
+    static KNOWN: OnceLock<Mutex<HashMap<Vec<Board>, i32>>> = OnceLock::new();
+
+    KNOWN.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn there_are_46_boards_that_are_alive() {
+        let table = table();
+        let mut alive = (0..1 << 9)
+            .filter(|&board| !table.dead[board])
+            .map(|board| table.canonical[board])
+            .collect::<Vec<_>>();
+
+        alive.sort_unstable();
+        alive.dedup();
+
+        assert_eq!(alive.len(), 46);
+    }
+
+    #[test]
+    fn the_second_player_wins_on_2_boards() {
+        let game = NotaktoGame::start_with_boards(Mark::X, 2);
+
+        // Every move loses
+        assert_eq!(moves(&game, usize::MAX).len(), 18);
+    }
+
+    #[test]
+    fn it_finds_the_winning_replies() {
+        let mut game = NotaktoGame::start(Mark::X);
+
+        game.play(0, (0, 1)).unwrap();
+
+        assert_eq!(moves(&game, usize::MAX), vec![(0, (1, 0)), (0, (1, 2)), (0, (2, 1))]);
+    }
+
+    #[test]
+    fn every_opening_wins_on_3_boards() {
+        let game = NotaktoGame::start_with_boards(Mark::X, 3);
+
+        assert_eq!(moves(&game, usize::MAX), game.legal_moves());
+    }
+
+    #[test]
+    fn it_avoids_killing_the_last_board() {
+        let mut game = NotaktoGame::start_with_boards(Mark::X, 2);
+
+        for &(i, p) in &[(0, (0, 0)), (0, (0, 1)), (0, (0, 2)), (1, (0, 0)), (1, (2, 2))] {
+            game.play(i, p).unwrap();
+        }
+
+        // Every move loses, but the middle of the last board loses on the spot
+        assert_eq!(moves(&game, 1).len(), 6);
+        assert_eq!(moves(&game, usize::MAX).len(), 6);
+        assert!(!moves(&game, usize::MAX).contains(&(1, (1, 1))));
+    }
+}
@@ -0,0 +1,194 @@
+use std::sync::OnceLock;
+
+use crate::ai::best_moves;
+use crate::qubic::{ self, QubicGame, QubicPosition };
+
+/// Returns all the moves that are equally-best when looking `depth` moves ahead in the given
+/// `QubicGame`, in order of layer, row and then column.
+///
+/// Qubic is a win for the first player but far too big to search to the end, so the positions
+/// that are still in play after `depth` moves are valued by a heuristic instead. It counts the
+/// lines that each player has started and the other player hasn't blocked. A player who can
+/// win on the spot always does, and a player facing a line of 3 always blocks it, however deep
+/// the search.
+///
+/// # Examples
+///
+/// ```
+/// use xsos::{ Mark, QubicGame };
+/// use xsos::ai::qubic;
+///
+/// let mut game = QubicGame::start(Mark::X);
+///
+/// for c in 0..3 {
+///     game.play((0, 0, c)).unwrap();
+///     game.play((3, 3, c)).unwrap();
+/// }
+///
+/// // X wins on the spot rather than blocking O
+/// assert_eq!(qubic::moves(&game, 1), vec![(0, 0, 3)]);
+/// ```
+pub fn moves(game: &QubicGame, depth: usize) -> Vec<QubicPosition> {
+    if game.is_game_over() {
+        return Vec::new();
+    }
+
+    let ours = game.bits(game.turn());
+    let theirs = game.bits(game.turn().swap());
+    let best = best_moves(candidates(ours, theirs), -INFINITY, |i, best| {
+        let next = ours | 1 << i;
+
+        if qubic::is_win(next) {
+            WIN - 1
+        } else {
+            -negamax(theirs, next, depth.saturating_sub(1), 1, -INFINITY, -(best - 1))
+        }
+    });
+    let mut best = best.into_iter().map(qubic::to_position).collect::<Vec<_>>();
+
+    best.sort_unstable();
+    best
+}
+
+/// The score of a win on the spot. It's far larger than any heuristic value so that a forced
+/// win always beats a good looking position.
+const WIN: i32 = 100_000;
+
+const INFINITY: i32 = WIN + 1;
+
+/// The value of a line that a player has 1, 2 or 3 marks on, and the other player none.
+const LINE: [i32; 4] = [0, 1, 10, 100];
+
+/// Returns the value, for the player to move, of the position where they've marked the cells
+/// of `ours` and the other player has marked the cells of `theirs`, looking `depth` moves ahead.
+/// A win `ply` moves from the root is worth less than one found sooner.
+fn negamax(ours: u64, theirs: u64, depth: usize, ply: i32, mut alpha: i32, beta: i32) -> i32 {
+    let empty = !(ours | theirs);
+
+    if empty == 0 {
+        return 0;
+    }
+
+    if threats(ours, empty) != 0 {
+        return WIN - (ply + 1);
+    }
+
+    if depth == 0 {
+        return heuristic(ours, theirs);
+    }
+
+    let mut value = -INFINITY;
+
+    for i in candidates(ours, theirs) {
+        value = value.max(-negamax(theirs, ours | 1 << i, depth - 1, ply + 1, -beta, -alpha));
+        alpha = alpha.max(value);
+
+        if alpha >= beta {
+            break;
+        }
+    }
+
+    value
+}
+
+/// Returns the cells worth searching for the player to move, best first.
+///
+/// That's only the cells that win on the spot, if there are any, or else the cells that block
+/// a line of 3, if the other player has one, and otherwise every unmarked cell, starting with
+/// those on the most lines.
+fn candidates(ours: u64, theirs: u64) -> Vec<usize> {
+    let empty = !(ours | theirs);
+    let wins = threats(ours, empty);
+    let blocks = threats(theirs, empty);
+    let cells = if wins != 0 { wins } else if blocks != 0 { blocks } else { empty };
+
+    order().iter().copied().filter(|&i| cells & 1 << i != 0).collect()
+}
+
+/// Returns the unmarked cells, out of `empty`, that would complete a line of `bits`.
+fn threats(bits: u64, empty: u64) -> u64 {
+    qubic::lines()
+        .iter()
+        .filter(|&&line| (bits & line).count_ones() == 3 && line & empty != 0)
+        .fold(0, |cells, &line| cells | (line & empty))
+}
+
+/// Returns every cell, those on the most lines first. The 8 corners and the 8 cells in the
+/// middle of the cube are on 7 lines each and the rest are on 4.
+fn order() -> &'static [usize] {
+    static ORDER: OnceLock<Vec<usize>> = OnceLock::new();
+
+    ORDER.get_or_init(|| {
+        let lines = qubic::lines();
+        let mut cells = (0..64).collect::<Vec<usize>>();
+
+        cells.sort_by_key(|&i| std::cmp::Reverse(lines.iter().filter(|&&line| line & 1 << i != 0).count()));
+        cells
+    })
+}
+
+/// Values the position for the player who marked the cells of `ours`.
+fn heuristic(ours: u64, theirs: u64) -> i32 {
+    qubic::lines()
+        .iter()
+        .map(|&line| {
+            match ((ours & line).count_ones() as usize, (theirs & line).count_ones() as usize) {
+                (n, 0) => LINE[n],
+                (0, n) => -LINE[n],
+                _ => 0
+            }
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mark::Mark;
+
+    #[test]
+    fn it_blocks_a_line_of_3() {
+        let mut game = QubicGame::start(Mark::X);
+
+        for &p in &[(1, 1, 2), (0, 0, 0), (2, 2, 1), (0, 3, 3), (0, 0, 3)] {
+            game.play(p).unwrap();
+        }
+
+        // X threatens a diagonal through the middle of the cube at (3, 3, 0), so O has to block it
+        assert_eq!(game.turn(), Mark::O);
+        assert_eq!(moves(&game, 1), vec![(3, 3, 0)]);
+        assert_eq!(moves(&game, 3), vec![(3, 3, 0)]);
+    }
+
+    #[test]
+    fn it_sets_up_two_threats_at_once() {
+        let mut game = QubicGame::start(Mark::X);
+
+        // X has 2 on the top row and 2 down the left column of the top layer, and playing the
+        // corner where they meet makes two lines of 3 that O can't both block
+        for &p in &[(0, 0, 1), (3, 3, 3), (0, 0, 2), (3, 3, 0), (0, 1, 0), (3, 0, 3), (0, 2, 0), (3, 0, 0)] {
+            game.play(p).unwrap();
+        }
+
+        assert_eq!(moves(&game, 3), vec![(0, 0, 0)]);
+    }
+
+    #[test]
+    fn the_corners_and_the_middle_come_first() {
+        let order = order();
+
+        assert_eq!(order.len(), 64);
+        assert!(order[..16].contains(&0));
+        assert!(order[..16].contains(&21));
+        assert!(!order[..16].contains(&1));
+    }
+
+    #[test]
+    fn the_heuristic_is_symmetric() {
+        let ours = qubic::to_bit((1, 1, 1)) | qubic::to_bit((0, 0, 0));
+        let theirs = qubic::to_bit((2, 1, 3));
+
+        assert_eq!(heuristic(ours, theirs), -heuristic(theirs, ours));
+        assert!(heuristic(ours, theirs) > 0);
+    }
+}
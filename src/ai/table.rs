@@ -0,0 +1,227 @@
+use std::collections::HashMap;
+
+use crate::bits::Bits;
+use crate::grid::{ Grid, Position, Shape };
+use crate::mark::Mark;
+use crate::referee::Rules;
+
+/// A cache of the values of the positions that have already been searched.
+///
+/// Positions are keyed by a canonical form of their grid which is the same for all the
+/// rotations and reflections of a grid. So, for e.g., all four of the positions where
+/// X marked a corner on the first move share one entry.
+///
+/// # Examples
+///
+/// ```
+/// use xsos::{ Game, Mark };
+/// use xsos::ai::{ Solver, Value };
+///
+/// let mut solver = Solver::new().with_pruning(false);
+///
+/// assert_eq!(solver.solve(&Game::start(Mark::X)), Value::Draw);
+///
+/// // Only the essentially different positions were searched
+/// assert_eq!(solver.table().len(), 765);
+/// assert_eq!(solver.table().stats().misses, 765);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct TranspositionTable {
+    entries: HashMap<Key, Entry>,
+    stats: Stats
+}
+
+/// The number of times a [`TranspositionTable`] was able, or unable, to provide the value of a position.
+///
+/// [`TranspositionTable`]: ./struct.TranspositionTable.html
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct Stats {
+    /// The number of lookups that saved a search.
+    pub hits: u64,
+
+    /// The number of lookups that led to a search.
+    pub misses: u64
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub(super) struct Key {
+    shape: Shape,
+    rules: Rules,
+    turn: Mark,
+    xs: Bits,
+    os: Bits
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(super) struct Entry {
+    pub value: i16,
+    pub bound: Bound
+}
+
+/// How the value of an [`Entry`] relates to the true value of its position.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub(super) enum Bound {
+    Exact,
+    Lower,
+    Upper
+}
+
+impl TranspositionTable {
+    /// Creates a new empty `TranspositionTable`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of positions in this `TranspositionTable`.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if this `TranspositionTable` has no positions.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the hit/miss statistics of this `TranspositionTable`.
+    pub fn stats(&self) -> Stats {
+        self.stats
+    }
+
+    /// Removes all the positions and resets the statistics.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.stats = Stats::default();
+    }
+
+    /// Returns the value stored for the `key` if it's good enough to be used
+    /// in place of a search with the window `[alpha, beta]`.
+    pub(super) fn probe(&mut self, key: &Key, alpha: i16, beta: i16) -> Option<i16> {
+        let value = self.entries.get(key).and_then(|&Entry { value, bound }| {
+            match bound {
+                Bound::Exact => Some(value),
+                Bound::Lower if value >= beta => Some(value),
+                Bound::Upper if value <= alpha => Some(value),
+                _ => None
+            }
+        });
+
+        if value.is_some() {
+            self.stats.hits += 1;
+        } else {
+            self.stats.misses += 1;
+        }
+
+        value
+    }
+
+    pub(super) fn store(&mut self, key: Key, entry: Entry) {
+        self.entries.insert(key, entry);
+    }
+}
+
+impl Key {
+    /// Creates the canonical `Key` for the position given by the `grid` and the player whose `turn` it is,
+    /// under the given `rules`.
+    pub(super) fn new(grid: &Grid, turn: Mark, rules: Rules) -> Self {
+        let shape = grid.shape();
+        let (xs, os) = symmetries(shape)
+            .map(|transform| {
+                let mut xs = Bits::default();
+                let mut os = Bits::default();
+
+                for pos in (0..shape.rows()).flat_map(|r| (0..shape.columns()).map(move |c| (r, c))) {
+                    match grid.cell_at(transform(pos)) {
+                        Some(Mark::X) => xs.set(shape.to_bit(pos)),
+                        Some(Mark::O) => os.set(shape.to_bit(pos)),
+                        None => ()
+                    }
+                }
+
+                (xs, os)
+            })
+            .min()
+            .unwrap();
+
+        Self { shape, rules, turn, xs, os }
+    }
+}
+
+/// Returns the rotations and reflections that map a grid of the given `Shape` onto itself.
+///
+/// A square grid has the 8 symmetries of the square. Any other grid only has 4 since
+/// it can't be rotated by a quarter turn.
+fn symmetries(shape: Shape) -> impl Iterator<Item = impl Fn(Position) -> Position> {
+    let rows = shape.rows();
+    let columns = shape.columns();
+    let count = if rows == columns { 8 } else { 4 };
+
+    (0..count).map(move |i| {
+        move |(r, c): Position| {
+            let (mr, mc) = (rows - 1 - r, columns - 1 - c);
+
+            match i {
+                0 => (r, c),
+                1 => (mr, mc),
+                2 => (r, mc),
+                3 => (mr, c),
+                4 => (c, r),
+                5 => (mc, mr),
+                6 => (c, mr),
+                _ => (mc, r)
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::referee::Variant;
+
+    #[test]
+    fn rotations_and_reflections_share_a_key() {
+        let keys = [(0, 0), (0, 2), (2, 0), (2, 2)]
+            .iter()
+            .map(|&pos| {
+                let mut grid = Grid::new();
+
+                grid.mark(pos, Mark::X);
+                grid.mark((0, 1), Mark::O);
+                Key::new(&grid, Mark::X, Rules::default())
+            })
+            .collect::<Vec<_>>();
+
+        // (0, 0) and (0, 2) are reflections of each other, as are (2, 0) and (2, 2)
+        assert_eq!(keys[0], keys[1]);
+        assert_eq!(keys[2], keys[3]);
+        assert_ne!(keys[0], keys[2]);
+    }
+
+    #[test]
+    fn the_turn_is_part_of_the_key() {
+        let grid = Grid::new();
+
+        assert_ne!(Key::new(&grid, Mark::X, Rules::default()), Key::new(&grid, Mark::O, Rules::default()));
+    }
+
+    #[test]
+    fn the_rules_are_part_of_the_key() {
+        let grid = Grid::new();
+        let misere = Rules { variant: Variant::Misere, ..Rules::default() };
+
+        assert_ne!(Key::new(&grid, Mark::X, Rules::default()), Key::new(&grid, Mark::X, misere));
+    }
+
+    #[test]
+    fn a_rectangular_grid_cannot_be_transposed() {
+        let shape = Shape::new(2, 3, 2);
+        let mut a = Grid::with_shape(shape);
+        let mut b = Grid::with_shape(shape);
+
+        a.mark((0, 0), Mark::X);
+        b.mark((1, 2), Mark::X);
+
+        assert_eq!(Key::new(&a, Mark::O, Rules::default()), Key::new(&b, Mark::O, Rules::default()));
+        assert_eq!(symmetries(shape).count(), 4);
+    }
+}
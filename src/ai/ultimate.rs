@@ -0,0 +1,177 @@
+use std::sync::OnceLock;
+
+use crate::ai::best_moves;
+use crate::grid::{ Position, Shape };
+use crate::mark::Mark;
+use crate::referee::{ self, Line, Outcome };
+use crate::ultimate::{ UltimateGame, UltimateMove };
+
+/// Returns all the moves that are equally-best when looking `depth` moves ahead in the given
+/// `UltimateGame`, in the order of [`UltimateGame::legal_moves`].
+///
+/// Ultimate Tic-tac-toe is far too big to search to the end, so the positions that are still
+/// in play after `depth` moves are valued by a heuristic instead. It counts the boards each
+/// player has won and the lines, on the meta-grid and on the open boards, that each player
+/// has started and the other player hasn't blocked.
+///
+/// # Examples
+///
+/// ```
+/// use xsos::{ Mark, UltimateGame };
+/// use xsos::ai::ultimate;
+///
+/// let mut game = UltimateGame::start(Mark::X);
+///
+/// for &(board, cell) in &[((0, 0), (0, 2)), ((0, 2), (0, 0)), ((0, 0), (1, 1)), ((1, 1), (0, 0))] {
+///     game.play(board, cell).unwrap();
+/// }
+///
+/// // X takes the top left board
+/// assert_eq!(ultimate::moves(&game, 2), vec![((0, 0), (2, 0))]);
+/// ```
+///
+/// [`UltimateGame::legal_moves`]: ../../struct.UltimateGame.html#method.legal_moves
+pub fn moves(game: &UltimateGame, depth: usize) -> Vec<UltimateMove> {
+    let mut game = game.clone();
+
+    best_moves(game.legal_moves(), -INFINITY, |mv, best| {
+        game.unchecked_play(mv);
+
+        let value = -negamax(&mut game, depth.saturating_sub(1), 1, -INFINITY, -(best - 1));
+
+        game.undo();
+
+        value
+    })
+}
+
+/// The score of a win on the spot. It's far larger than any heuristic value so that a forced
+/// win always beats a good looking position.
+const WIN: i32 = 100_000;
+
+const INFINITY: i32 = WIN + 1;
+
+/// Returns the value of the `game` for the player whose turn it is, looking `depth` moves
+/// ahead. A win `ply` moves from the root is worth less than one found sooner.
+fn negamax(game: &mut UltimateGame, depth: usize, ply: i32, mut alpha: i32, beta: i32) -> i32 {
+    match game.outcome() {
+        // Only the player who made the last move can have won
+        Some(Outcome::Win(_)) => return -(WIN - ply),
+        Some(Outcome::Draw) => return 0,
+        None if depth == 0 => return heuristic(game, game.turn()),
+        None => ()
+    }
+
+    let mut value = -INFINITY;
+
+    for mv in game.legal_moves() {
+        game.unchecked_play(mv);
+        value = value.max(-negamax(game, depth - 1, ply + 1, -beta, -alpha));
+        game.undo();
+
+        alpha = alpha.max(value);
+
+        if alpha >= beta {
+            break;
+        }
+    }
+
+    value
+}
+
+/// The positions of the boards on the meta-grid, or of the cells on a board.
+const POSITIONS: [Position; 9] = [(0, 0), (0, 1), (0, 2), (1, 0), (1, 1), (1, 2), (2, 0), (2, 1), (2, 2)];
+
+/// The value of a board that's been won, and of the centre board on top of that.
+const BOARD: i32 = 100;
+const CENTRE_BOARD: i32 = 50;
+
+/// The value of a line that a player has 1 or 2 marks on, and the other player none, on the
+/// meta-grid and on a board.
+const META_LINE: [i32; 3] = [0, 20, 80];
+const BOARD_LINE: [i32; 3] = [0, 1, 5];
+
+/// Values the `game`, which is in play, for the player with the given `mark`.
+fn heuristic(game: &UltimateGame, mark: Mark) -> i32 {
+    let mut value = 0;
+
+    for &board in &POSITIONS {
+        match game.board_outcome(board) {
+            Some(Outcome::Win(winner)) => {
+                let won = if board == (1, 1) { BOARD + CENTRE_BOARD } else { BOARD };
+
+                value += if winner == mark { won } else { -won };
+            },
+            Some(Outcome::Draw) => (),
+            None => {
+                let grid = game.board(board);
+
+                value += lines(|p| grid.cell_at(p).map(Outcome::Win), mark, &BOARD_LINE);
+            }
+        }
+    }
+
+    // A drawn board blocks every line of boards through it
+    value + lines(|board| game.board_outcome(board), mark, &META_LINE)
+}
+
+/// Sums up the `weights` of the lines that only one player holds positions on, by how many
+/// positions they hold, for the player with the given `mark`.
+///
+/// Who holds a position is given by `holder`, where a `Draw` blocks the line for both players.
+fn lines(holder: impl Fn(Position) -> Option<Outcome>, mark: Mark, weights: &[i32; 3]) -> i32 {
+    static LINES: OnceLock<Vec<Line>> = OnceLock::new();
+
+    LINES
+        .get_or_init(|| referee::lines(Shape::default()).collect())
+        .iter()
+        .map(|line| {
+            let holders = line.positions().filter_map(&holder).collect::<Vec<_>>();
+            let ours = holders.iter().filter(|&&h| h == Outcome::Win(mark)).count();
+
+            match (ours, holders.len() - ours) {
+                (n, 0) if n < 3 => weights[n],
+                (0, n) if n < 3 && !holders.contains(&Outcome::Draw) => -weights[n],
+                _ => 0
+            }
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn play_all(game: &mut UltimateGame, moves: &[UltimateMove]) {
+        for &(board, cell) in moves {
+            game.play(board, cell).unwrap();
+        }
+    }
+
+    #[test]
+    fn it_wins_the_game_when_it_can() {
+        let mut game = UltimateGame::start(Mark::X);
+
+        // X has won the top middle and top right boards, and is sent to the top left one
+        play_all(&mut game, &[
+            ((0, 2), (0, 0)), ((0, 0), (0, 1)), ((0, 1), (0, 0)), ((0, 0), (0, 0)), ((0, 0), (0, 2)),
+            ((0, 2), (0, 1)), ((0, 1), (0, 2)), ((0, 2), (0, 2)), ((0, 2), (2, 2)), ((2, 2), (0, 2)),
+            ((0, 2), (1, 1)), ((1, 1), (0, 0)), ((0, 0), (1, 1)), ((1, 1), (0, 1)), ((0, 1), (0, 1)),
+            ((1, 0), (0, 0))
+        ]);
+
+        assert_eq!(game.turn(), Mark::X);
+        assert_eq!(moves(&game, 1), vec![((0, 0), (2, 0))]);
+        assert_eq!(moves(&game, 3), vec![((0, 0), (2, 0))]);
+    }
+
+    #[test]
+    fn the_heuristic_is_symmetric() {
+        let mut game = UltimateGame::start(Mark::X);
+
+        play_all(&mut game, &[((1, 1), (0, 0)), ((0, 0), (1, 1)), ((1, 1), (2, 2)), ((2, 2), (0, 1))]);
+
+        assert_eq!(heuristic(&game, Mark::X), -heuristic(&game, Mark::O));
+        assert!(heuristic(&game, Mark::X) > 0);
+    }
+}
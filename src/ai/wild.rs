@@ -0,0 +1,132 @@
+use crate::ai::best_moves;
+use crate::grid::{ Grid, Position };
+use crate::mark::Mark;
+use crate::referee;
+use crate::wild::{ WildGame, WildMove };
+
+/// Returns all the moves that are equally-best when looking `depth` moves ahead in the given
+/// `WildGame`, in the order of [`WildGame::legal_moves`].
+///
+/// Every unmarked cell can take either mark, so there are twice as many moves to search as in a
+/// [`Game`] on the same grid. Positions that are still in play after `depth` moves are valued as
+/// draws. Looking as many moves ahead as there are unmarked cells gives the exact value of each
+/// move, which on the 3x3 grid is quick enough to do from the start.
+///
+/// # Examples
+///
+/// ```
+/// use xsos::{ Mark, WildGame };
+/// use xsos::ai::wild;
+///
+/// let mut game = WildGame::start(Mark::X);
+///
+/// game.play((1, 1), Mark::O).unwrap();
+/// game.play((0, 0), Mark::X).unwrap();
+///
+/// // Only 4 of X's moves don't leave O a line to complete
+/// assert_eq!(wild::moves(&game, 1).len(), 4);
+///
+/// // But looking a move further ahead X sees that an x in the opposite corner leaves O without
+/// // any such move
+/// assert_eq!(wild::moves(&game, 2), vec![((2, 2), Mark::X)]);
+/// ```
+///
+/// [`Game`]: ../../struct.Game.html
+/// [`WildGame::legal_moves`]: ../../struct.WildGame.html#method.legal_moves
+pub fn moves(game: &WildGame, depth: usize) -> Vec<WildMove> {
+    let mut grid = game.grid().clone();
+    let last = grid.last_mark();
+
+    best_moves(game.legal_moves(), -INFINITY, |(p, m), best| {
+        grid.mark(p, m);
+
+        let value = if referee::is_win(&grid) {
+            WIN - 1
+        } else {
+            -negamax(&mut grid, depth.saturating_sub(1), 1, -INFINITY, -(best - 1))
+        };
+
+        grid.unmark(p, last);
+
+        value
+    })
+}
+
+/// The score of a win on the spot.
+const WIN: i32 = 1_000;
+
+const INFINITY: i32 = WIN + 1;
+
+/// Returns the value of the position on the `grid` for the player to move, looking `depth` moves
+/// ahead. A win `ply` moves from the root is worth less than one found sooner.
+fn negamax(grid: &mut Grid, depth: usize, ply: i32, mut alpha: i32, beta: i32) -> i32 {
+    let empty = grid.unmarked_positions().collect::<Vec<_>>();
+
+    // A cell where either mark completes a line wins for whoever plays there first
+    if can_win(grid, &empty) {
+        return WIN - (ply + 1);
+    }
+
+    if empty.is_empty() || depth == 0 {
+        return 0;
+    }
+
+    let last = grid.last_mark();
+    let mut value = -INFINITY;
+
+    for &p in &empty {
+        for &m in &[Mark::X, Mark::O] {
+            grid.mark(p, m);
+            value = value.max(-negamax(grid, depth - 1, ply + 1, -beta, -alpha));
+            grid.unmark(p, last);
+
+            alpha = alpha.max(value);
+
+            if alpha >= beta {
+                return value;
+            }
+        }
+    }
+
+    value
+}
+
+/// Returns `true` if either mark completes a line at one of the `empty` cells of the `grid`.
+fn can_win(grid: &mut Grid, empty: &[Position]) -> bool {
+    let last = grid.last_mark();
+
+    empty.iter().any(|&p| {
+        [Mark::X, Mark::O].iter().any(|&m| {
+            grid.mark(p, m);
+            let is_win = referee::is_win(grid);
+            grid.unmark(p, last);
+
+            is_win
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_completes_a_line_of_either_mark() {
+        let mut game = WildGame::start(Mark::X);
+
+        game.play((0, 0), Mark::X).unwrap();
+        game.play((2, 2), Mark::O).unwrap();
+        game.play((0, 2), Mark::O).unwrap();
+
+        // An o completes the right column
+        assert_eq!(moves(&game, 1), vec![((1, 2), Mark::O)]);
+        assert_eq!(moves(&game, 9), vec![((1, 2), Mark::O)]);
+    }
+
+    #[test]
+    fn the_first_player_wins_by_taking_the_middle() {
+        let game = WildGame::start(Mark::O);
+
+        assert_eq!(moves(&game, 9), vec![((1, 1), Mark::X), ((1, 1), Mark::O)]);
+    }
+}
@@ -0,0 +1,140 @@
+use std::ops::{ BitAnd, BitOr, Not, Shr };
+
+/// The number of bits in a `Bits`.
+pub const CAPACITY: usize = 256;
+
+const WORDS: usize = CAPACITY / 64;
+
+/// A fixed size set of bits.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Default)]
+pub struct Bits([u64; WORDS]);
+
+impl Bits {
+    /// Returns a `Bits` with only the bit at `i` set.
+    #[cfg(test)]
+    pub fn bit(i: usize) -> Self {
+        let mut bits = Self::default();
+
+        bits.set(i);
+        bits
+    }
+
+    pub fn get(&self, i: usize) -> bool {
+        self.0[i / 64] & (1 << (i % 64)) != 0
+    }
+
+    pub fn set(&mut self, i: usize) {
+        self.0[i / 64] |= 1 << (i % 64);
+    }
+
+    pub fn clear(&mut self, i: usize) {
+        self.0[i / 64] &= !(1 << (i % 64));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.iter().all(|&word| word == 0)
+    }
+
+    pub fn count(&self) -> usize {
+        self.0.iter().map(|word| word.count_ones() as usize).sum()
+    }
+
+    /// Returns an iterator over the indices of the set bits, in increasing order.
+    pub fn ones(self) -> impl Iterator<Item = usize> {
+        (0..CAPACITY).filter(move |&i| self.get(i))
+    }
+}
+
+impl BitAnd for Bits {
+    type Output = Self;
+
+    fn bitand(self, other: Self) -> Self {
+        let mut bits = self;
+
+        for (a, b) in bits.0.iter_mut().zip(other.0.iter()) {
+            *a &= b;
+        }
+
+        bits
+    }
+}
+
+impl BitOr for Bits {
+    type Output = Self;
+
+    fn bitor(self, other: Self) -> Self {
+        let mut bits = self;
+
+        for (a, b) in bits.0.iter_mut().zip(other.0.iter()) {
+            *a |= b;
+        }
+
+        bits
+    }
+}
+
+impl Not for Bits {
+    type Output = Self;
+
+    fn not(self) -> Self {
+        let mut bits = self;
+
+        for a in bits.0.iter_mut() {
+            *a = !*a;
+        }
+
+        bits
+    }
+}
+
+impl Shr<usize> for Bits {
+    type Output = Self;
+
+    /// Moves every bit at `i` to `i - n`, dropping the bits at `0..n`.
+    fn shr(self, n: usize) -> Self {
+        let mut bits = Self::default();
+        let (words, n) = (n / 64, n % 64);
+
+        for i in 0..WORDS.saturating_sub(words) {
+            let lo = self.0[i + words] >> n;
+            let hi = match self.0.get(i + words + 1) {
+                Some(&word) if n > 0 => word << (64 - n),
+                _ => 0
+            };
+
+            bits.0[i] = lo | hi;
+        }
+
+        bits
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shifting_across_words() {
+        let bits = Bits::bit(0) | Bits::bit(64) | Bits::bit(200) | Bits::bit(255);
+
+        assert_eq!(bits >> 1, Bits::bit(63) | Bits::bit(199) | Bits::bit(254));
+        assert_eq!(bits >> 64, Bits::bit(0) | Bits::bit(136) | Bits::bit(191));
+        assert_eq!(bits >> 100, Bits::bit(100) | Bits::bit(155));
+        assert!((bits >> CAPACITY).is_empty());
+    }
+
+    #[test]
+    fn set_get_and_clear() {
+        let mut bits = Bits::default();
+
+        bits.set(3);
+        bits.set(130);
+
+        assert!(bits.get(3) && bits.get(130) && !bits.get(4));
+        assert_eq!(bits.count(), 2);
+
+        bits.clear(3);
+
+        assert_eq!(bits, Bits::bit(130));
+    }
+}
@@ -1,12 +1,112 @@
+use std::fs;
+use std::path::{ Path, PathBuf };
+use std::process;
+
+use structopt::clap::AppSettings;
 use structopt::StructOpt;
 
-use crate::Mark;
+use crate::ai;
+use crate::{ Difficulty, DrawRule, Game, Mark, NotaktoGame, QubicGame, Record, Strategy, UltimateGame, Variant, WildGame };
 
-mod interactive;
-mod noninteractive;
+pub mod analysis;
+mod computer;
+pub mod interactive;
+pub mod noninteractive;
+mod notakto;
+mod qubic;
+pub mod replay;
+mod ultimate;
+mod wild;
 
-#[derive(StructOpt, Debug, PartialEq, Clone, Copy)]
+/// The command line, where the options for `play` can be given without the subcommand so that
+/// plain `xsos` plays a game. Those options can't be followed by a subcommand, which wouldn't use
+/// them.
+#[derive(StructOpt, Debug, PartialEq, Clone)]
+#[structopt(setting = AppSettings::ArgsNegateSubcommands)]
 pub struct Config {
+    #[structopt(flatten)]
+    play: Play,
+
+    #[structopt(subcommand)]
+    command: Option<Command>
+}
+
+#[derive(StructOpt, Debug, PartialEq, Clone)]
+pub enum Command {
+    /// Plays against the computer, or another human
+    Play(Play),
+
+    /// Lets the computer play against itself
+    #[structopt(name = "selfplay")]
+    SelfPlay(SelfPlay),
+
+    /// Evaluates every move in a position, written as for e.g. "x.o/.x./... o", and shows the best
+    /// moves and how the game goes on from there with perfect play
+    Analyze {
+        /// Prints the analysis as JSON, where positions are 0-based [r, c] pairs
+        #[structopt(long)]
+        json: bool,
+
+        #[structopt
+            ( long
+            , default_value = "standard"
+            , parse(try_from_str = parse_variant)
+            )
+        ]
+        variant: Variant,
+
+        #[structopt(required = true)]
+        position: Vec<String>
+    },
+
+    /// Finds the result of a position with perfect play, written as for e.g. "x.o/.x./... o"
+    Solve {
+        #[structopt
+            ( long
+            , default_value = "standard"
+            , parse(try_from_str = parse_variant)
+            )
+        ]
+        variant: Variant,
+
+        #[structopt(required = true)]
+        position: Vec<String>
+    },
+
+    /// Steps through a game saved with "save <file>", showing what the computer thinks of each move
+    Replay {
+        #[structopt(parse(from_os_str))]
+        file: PathBuf
+    },
+
+    /// Plays Ultimate Tic-tac-toe, on a 3x3 grid of 3x3 boards
+    Ultimate(Versus),
+
+    /// Plays Qubic, i.e. Tic-tac-toe on a 4x4x4 cube
+    Qubic(Versus),
+
+    /// Plays Wild Tic-tac-toe, where either player can put down either mark
+    Wild(Versus),
+
+    /// Plays Notakto, where both players play x's and whoever kills the last board loses
+    Notakto {
+        #[structopt(flatten)]
+        versus: Versus,
+
+        /// The number of boards, from 1 to 4
+        #[structopt
+            ( short
+            , long
+            , default_value = "3"
+            , parse(try_from_str = parse_boards)
+            )
+        ]
+        boards: usize
+    }
+}
+
+#[derive(StructOpt, Debug, PartialEq, Clone)]
+pub struct Play {
     #[structopt
         ( short
         , default_value = "human"
@@ -32,32 +132,190 @@ pub struct Config {
     ]
     first: Mark,
 
+    /// The number of games to play when both players are computers
+    #[structopt
+        ( short
+        , long
+        , default_value = "25"
+        )
+    ]
+    rounds: u32,
+
+    #[structopt
+        ( short
+        , long
+        , default_value = "full"
+        , parse(try_from_str = parse_draw_rule)
+        )
+    ]
+    draw: DrawRule,
+
+    #[structopt
+        ( long
+        , default_value = "standard"
+        , parse(try_from_str = parse_variant)
+        )
+    ]
+    variant: Variant,
+
+    #[structopt(long)]
+    seed: Option<u64>,
+
+    /// Carries on from a game saved with "save <file>", which already says who moves first and
+    /// how the game is played
+    #[structopt
+        ( long
+        , parse(from_os_str)
+        , conflicts_with_all = &["first", "draw", "variant"]
+        )
+    ]
+    load: Option<PathBuf>
+}
+
+#[derive(StructOpt, Debug, PartialEq, Clone)]
+pub struct SelfPlay {
+    #[structopt
+        ( short
+        , default_value = "computer"
+        , parse(try_from_str = parse_computer)
+        )
+    ]
+    x: Difficulty,
+
+    #[structopt
+        ( short
+        , default_value = "computer"
+        , parse(try_from_str = parse_computer)
+        )
+    ]
+    o: Difficulty,
+
+    #[structopt
+        ( short
+        , long
+        , default_value = "x"
+        , parse(try_from_str = parse_mark)
+        )
+    ]
+    first: Mark,
+
     #[structopt
         ( short
         , long
         , default_value = "25"
         )
     ]
-    rounds: u8
+    rounds: u32,
+
+    #[structopt
+        ( short
+        , long
+        , default_value = "full"
+        , parse(try_from_str = parse_draw_rule)
+        )
+    ]
+    draw: DrawRule,
+
+    #[structopt
+        ( long
+        , default_value = "standard"
+        , parse(try_from_str = parse_variant)
+        )
+    ]
+    variant: Variant,
+
+    #[structopt(long)]
+    seed: Option<u64>
+}
+
+// The options for the games that only need to know who's playing, and who goes first. It isn't a
+// doc comment, which would replace the about text of the subcommands that flatten it.
+#[derive(StructOpt, Debug, PartialEq, Clone)]
+pub struct Versus {
+    #[structopt
+        ( short
+        , default_value = "human"
+        , parse(try_from_str = parse_player)
+        )
+    ]
+    x: Player,
+
+    #[structopt
+        ( short
+        , default_value = "computer"
+        , parse(try_from_str = parse_player)
+        )
+    ]
+    o: Player,
+
+    #[structopt
+        ( short
+        , long
+        , default_value = "x"
+        , parse(try_from_str = parse_mark)
+        )
+    ]
+    first: Mark,
+
+    #[structopt(long)]
+    seed: Option<u64>
 }
 
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+impl Config {
+    /// Returns the command to run, which is `play` unless another one was given.
+    pub fn command(self) -> Command {
+        self.command.unwrap_or(Command::Play(self.play))
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
 pub enum Player {
     Human,
-    Computer
+    Computer(Difficulty)
 }
 
 impl Player {
-    pub fn count_humans(players: &[Self]) -> u32 {
-        players.iter().fold(0, |sum, &p| if let Player::Human = p { sum + 1 } else { sum })
+    /// Returns the [`Strategy`] the computer uses to play for this player, or `None`
+    /// if it's a human.
+    ///
+    /// The strategy's random number generator is seeded by `seed`, if one is given.
+    ///
+    /// [`Strategy`]: ../trait.Strategy.html
+    pub fn strategy(self, seed: Option<u64>) -> Option<Box<dyn Strategy>> {
+        match (self, seed) {
+            (Player::Human, _) => None,
+            (Player::Computer(difficulty), None) => Some(difficulty.strategy()),
+            (Player::Computer(difficulty), Some(seed)) => Some(difficulty.seeded_strategy(seed))
+        }
     }
 }
 
 fn parse_player(src: &str) -> Result<Player, &'static str> {
-    match src.to_ascii_lowercase().as_ref() {
-        "h" | "human" => Ok(Player::Human),
-        "c" | "computer" => Ok(Player::Computer),
-        _ => Err("expected human|computer")
+    let src = src.to_ascii_lowercase();
+    let (name, epsilon) = match src.split_once(':') {
+        Some((name, epsilon)) => (name, Some(epsilon)),
+        None => (src.as_ref(), None)
+    };
+
+    match (name, epsilon) {
+        ("h" | "human", None) => Ok(Player::Human),
+        ("c" | "computer" | "hard", None) => Ok(Player::Computer(Difficulty::Hard)),
+        ("easy", None) => Ok(Player::Computer(Difficulty::Easy)),
+        ("medium", None) => Ok(Player::Computer(Difficulty::Medium)),
+        ("blunder", None) => Ok(Player::Computer(Difficulty::Blunder(0.1))),
+        ("blunder", Some(epsilon)) => match epsilon.parse::<f64>() {
+            Ok(epsilon) if (0.0..=1.0).contains(&epsilon) => Ok(Player::Computer(Difficulty::Blunder(epsilon))),
+            _ => Err("expected an epsilon between 0 and 1, for e.g. blunder:0.25")
+        },
+        _ => Err("expected human|computer|easy|medium|hard|blunder[:epsilon]")
+    }
+}
+
+fn parse_computer(src: &str) -> Result<Difficulty, &'static str> {
+    match parse_player(src) {
+        Ok(Player::Computer(difficulty)) => Ok(difficulty),
+        Ok(Player::Human) => Err("expected a computer, i.e. computer|easy|medium|hard|blunder[:epsilon]"),
+        Err(e) => Err(e)
     }
 }
 
@@ -69,13 +327,112 @@ fn parse_mark(src: &str) -> Result<Mark, &'static str> {
     }
 }
 
+fn parse_boards(src: &str) -> Result<usize, &'static str> {
+    match src.parse::<usize>() {
+        // The computer searches every position, which takes too long with more boards
+        Ok(boards) if (1..=4).contains(&boards) => Ok(boards),
+        _ => Err("expected a number of boards between 1 and 4")
+    }
+}
+
+fn parse_draw_rule(src: &str) -> Result<DrawRule, &'static str> {
+    match src.to_ascii_lowercase().as_ref() {
+        "full" => Ok(DrawRule::FullGrid),
+        "blocked" => Ok(DrawRule::Blocked),
+        "perfect" => Ok(DrawRule::PerfectPlay),
+        _ => Err("expected full|blocked|perfect")
+    }
+}
+
+fn parse_variant(src: &str) -> Result<Variant, &'static str> {
+    match src.to_lowercase().as_ref() {
+        "standard" => Ok(Variant::Standard),
+        "misere" | "misère" => Ok(Variant::Misere),
+        _ => Err("expected standard|misere")
+    }
+}
+
+/// Loads the `Record` saved at `path`, or exits with a non-zero status if it can't.
+fn load(path: &Path) -> Record {
+    let record = fs::read_to_string(path)
+        .map_err(|e| e.to_string())
+        .and_then(|record| record.parse::<Record>().map_err(|e| e.to_string()));
+
+    match record {
+        Ok(record) => record,
+        Err(e) => {
+            eprintln!("Unable to load {}: {}", path.display(), e);
+            process::exit(1)
+        }
+    }
+}
+
 pub fn run() {
-    let Config { x, o, first, rounds } = Config::from_args();
+    match Config::from_args().command() {
+        Command::Play(options) => play(options),
+        Command::SelfPlay(options) => self_play(options),
+        Command::Analyze { json, variant, position } => analysis::analyze(&parse_position(&position).with_variant(variant), json),
+        Command::Solve { variant, position } => analysis::solve(&parse_position(&position).with_variant(variant)),
+        Command::Replay { file } => replay::run(load(&file)),
+        Command::Ultimate(Versus { x, o, first, seed }) => computer::run(UltimateGame::start(first), x, o, seed),
+        Command::Qubic(Versus { x, o, first, seed }) => computer::run(QubicGame::start(first), x, o, seed),
+        Command::Wild(Versus { x, o, first, seed }) => computer::run(WildGame::start(first), x, o, seed),
+        Command::Notakto { versus: Versus { x, o, first, seed }, boards } => {
+            computer::run(NotaktoGame::start_with_boards(first, boards), x, o, seed)
+        }
+    }
+}
+
+fn play(Play { x, o, first, rounds, draw, variant, seed, load: path }: Play) {
+    let game = match path {
+        Some(path) => load(&path).into_game(),
+        None => Game::start(first).with_draw_rule(draw).with_variant(variant)
+    };
+
+    let (x_seed, o_seed) = seeds(seed);
+
+    match (x.strategy(x_seed), o.strategy(o_seed)) {
+        (Some(x), Some(o)) => noninteractive::run(game, rounds, x, o),
+        (x, o) => interactive::run(game, x, o)
+    }
+}
+
+fn self_play(SelfPlay { x, o, first, rounds, draw, variant, seed }: SelfPlay) {
+    let game = Game::start(first).with_draw_rule(draw).with_variant(variant);
+    let (x_seed, o_seed) = seeds(seed);
+    let x = Player::Computer(x).strategy(x_seed).unwrap();
+    let o = Player::Computer(o).strategy(o_seed).unwrap();
+
+    noninteractive::run(game, rounds, x, o);
+}
+
+/// Returns the seeds for X and O given a `seed` for the game. Each side gets its own seed so that
+/// they don't make the same random choices.
+fn seeds(seed: Option<u64>) -> (Option<u64>, Option<u64>) {
+    (seed, seed.map(|seed| seed.wrapping_add(1)))
+}
+
+/// Parses a position given as one or more arguments, which are put back together with spaces so
+/// that the position doesn't have to be quoted. It exits with a non-zero status if the position
+/// is invalid or too large for the computer to solve.
+fn parse_position(args: &[String]) -> Game {
+    match read_position(args) {
+        Ok(game) => game,
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(1)
+        }
+    }
+}
 
-    if let (Player::Computer, Player::Computer) = (x, o) {
-        noninteractive::run(first, rounds);
+fn read_position(args: &[String]) -> Result<Game, String> {
+    let game = args.join(" ").parse::<Game>().map_err(|e| format!("Invalid position: {}", e))?;
+    let shape = game.grid().shape();
+
+    if ai::can_solve(shape) {
+        Ok(game)
     } else {
-        interactive::run(first, x, o);
+        Err(format!("The grid is too large for the computer to solve, it can only search grids of up to {} cells", ai::MAX_SOLVABLE_CELLS))
     }
 }
 
@@ -83,15 +440,26 @@ pub fn run() {
 mod tests {
     use super::*;
 
+    fn play(args: &[&str]) -> Play {
+        match Config::from_iter(args).command() {
+            Command::Play(play) => play,
+            command => panic!("expected play, got {:?}", command)
+        }
+    }
+
     #[test]
     fn defaults() {
         assert_eq!(
-            Config::from_iter(&[""]),
-            Config {
+            play(&[""]),
+            Play {
                 x: Player::Human,
-                o: Player::Computer,
+                o: Player::Computer(Difficulty::Hard),
                 first: Mark::X,
-                rounds: 25
+                rounds: 25,
+                draw: DrawRule::FullGrid,
+                variant: Variant::Standard,
+                seed: None,
+                load: None
             }
         );
     }
@@ -99,12 +467,16 @@ mod tests {
     #[test]
     fn let_o_play_first() {
         assert_eq!(
-            Config::from_iter(&["", "--first", "o"]),
-            Config {
+            play(&["", "--first", "o"]),
+            Play {
                 x: Player::Human,
-                o: Player::Computer,
+                o: Player::Computer(Difficulty::Hard),
                 first: Mark::O,
-                rounds: 25
+                rounds: 25,
+                draw: DrawRule::FullGrid,
+                variant: Variant::Standard,
+                seed: None,
+                load: None
             }
         );
     }
@@ -112,34 +484,46 @@ mod tests {
     #[test]
     fn let_computer_play_with_x_and_human_play_with_o() {
         assert_eq!(
-            Config::from_iter(&["", "-x", "computer", "-o", "human"]),
-            Config {
-                x: Player::Computer,
+            play(&["", "-x", "computer", "-o", "human"]),
+            Play {
+                x: Player::Computer(Difficulty::Hard),
                 o: Player::Human,
                 first: Mark::X,
-                rounds: 25
+                rounds: 25,
+                draw: DrawRule::FullGrid,
+                variant: Variant::Standard,
+                seed: None,
+                load: None
             }
         );
 
         // Shorthand
         assert_eq!(
-            Config::from_iter(&["", "-x", "c", "-o", "h"]),
-            Config {
-                x: Player::Computer,
+            play(&["", "-x", "c", "-o", "h"]),
+            Play {
+                x: Player::Computer(Difficulty::Hard),
                 o: Player::Human,
                 first: Mark::X,
-                rounds: 25
+                rounds: 25,
+                draw: DrawRule::FullGrid,
+                variant: Variant::Standard,
+                seed: None,
+                load: None
             }
         );
 
         // Case insensitive
         assert_eq!(
-            Config::from_iter(&["", "-x", "cOmPuTeR", "-o", "H"]),
-            Config {
-                x: Player::Computer,
+            play(&["", "-x", "cOmPuTeR", "-o", "H"]),
+            Play {
+                x: Player::Computer(Difficulty::Hard),
                 o: Player::Human,
                 first: Mark::X,
-                rounds: 25
+                rounds: 25,
+                draw: DrawRule::FullGrid,
+                variant: Variant::Standard,
+                seed: None,
+                load: None
             }
         );
     }
@@ -147,12 +531,16 @@ mod tests {
     #[test]
     fn computer_vs_computer() {
         assert_eq!(
-            Config::from_iter(&["", "-x", "computer"]),
-            Config {
-                x: Player::Computer,
-                o: Player::Computer,
+            play(&["", "-x", "computer"]),
+            Play {
+                x: Player::Computer(Difficulty::Hard),
+                o: Player::Computer(Difficulty::Hard),
                 first: Mark::X,
-                rounds: 25
+                rounds: 25,
+                draw: DrawRule::FullGrid,
+                variant: Variant::Standard,
+                seed: None,
+                load: None
             }
         );
     }
@@ -160,13 +548,248 @@ mod tests {
     #[test]
     fn computer_vs_computer_for_50_rounds() {
         assert_eq!(
-            Config::from_iter(&["", "-x", "computer", "--rounds", "50"]),
-            Config {
-                x: Player::Computer,
-                o: Player::Computer,
+            play(&["", "-x", "computer", "--rounds", "50"]),
+            Play {
+                x: Player::Computer(Difficulty::Hard),
+                o: Player::Computer(Difficulty::Hard),
+                first: Mark::X,
+                rounds: 50,
+                draw: DrawRule::FullGrid,
+                variant: Variant::Standard,
+                seed: None,
+                load: None
+            }
+        );
+    }
+
+    #[test]
+    fn choose_how_hard_the_computer_plays() {
+        assert_eq!(
+            play(&["", "-x", "easy", "-o", "blunder:0.25"]),
+            Play {
+                x: Player::Computer(Difficulty::Easy),
+                o: Player::Computer(Difficulty::Blunder(0.25)),
+                first: Mark::X,
+                rounds: 25,
+                draw: DrawRule::FullGrid,
+                variant: Variant::Standard,
+                seed: None,
+                load: None
+            }
+        );
+
+        assert_eq!(parse_player("Medium"), Ok(Player::Computer(Difficulty::Medium)));
+        assert_eq!(parse_player("hard"), Ok(Player::Computer(Difficulty::Hard)));
+        assert_eq!(parse_player("blunder"), Ok(Player::Computer(Difficulty::Blunder(0.1))));
+        assert!(parse_player("blunder:2").is_err());
+        assert!(parse_player("human:0.5").is_err());
+    }
+
+    #[test]
+    fn declare_draws_early() {
+        assert_eq!(
+            play(&["", "-x", "computer", "--draw", "blocked"]),
+            Play {
+                x: Player::Computer(Difficulty::Hard),
+                o: Player::Computer(Difficulty::Hard),
+                first: Mark::X,
+                rounds: 25,
+                draw: DrawRule::Blocked,
+                variant: Variant::Standard,
+                seed: None,
+                load: None
+            }
+        );
+    }
+
+    #[test]
+    fn reproduce_a_run_with_a_seed() {
+        assert_eq!(
+            play(&["", "-x", "easy", "-o", "medium", "--seed", "42"]),
+            Play {
+                x: Player::Computer(Difficulty::Easy),
+                o: Player::Computer(Difficulty::Medium),
+                first: Mark::X,
+                rounds: 25,
+                draw: DrawRule::FullGrid,
+                variant: Variant::Standard,
+                seed: Some(42),
+                load: None
+            }
+        );
+    }
+
+    #[test]
+    fn carry_on_from_a_saved_game() {
+        assert_eq!(
+            play(&["", "--load", "game.txt"]),
+            Play {
+                x: Player::Human,
+                o: Player::Computer(Difficulty::Hard),
+                first: Mark::X,
+                rounds: 25,
+                draw: DrawRule::FullGrid,
+                variant: Variant::Standard,
+                seed: None,
+                load: Some(PathBuf::from("game.txt"))
+            }
+        );
+    }
+
+    #[test]
+    fn a_saved_game_says_how_it_is_played() {
+        assert!(Config::from_iter_safe(&["", "--load", "game.txt", "--first", "o"]).is_err());
+        assert!(Config::from_iter_safe(&["", "--load", "game.txt", "--draw", "blocked"]).is_err());
+        assert!(Config::from_iter_safe(&["", "play", "--load", "game.txt", "--variant", "misere"]).is_err());
+
+        assert_eq!(play(&["", "--load", "game.txt", "-x", "easy"]).x, Player::Computer(Difficulty::Easy));
+    }
+
+    #[test]
+    fn play_options_are_not_ignored_before_a_subcommand() {
+        assert!(Config::from_iter_safe(&["", "-x", "computer", "selfplay"]).is_err());
+        assert!(Config::from_iter_safe(&["", "--seed", "1", "qubic"]).is_err());
+    }
+
+    #[test]
+    fn play_the_misere_variant() {
+        assert_eq!(play(&["", "--variant", "misere"]).variant, Variant::Misere);
+        assert_eq!(play(&["", "--variant", "misère"]).variant, Variant::Misere);
+
+        assert!(Config::from_iter_safe(&["", "--variant", "reverse"]).is_err());
+    }
+
+    #[test]
+    fn play_is_the_default_command() {
+        assert_eq!(play(&["", "play", "-x", "easy", "--first", "o"]), play(&["", "-x", "easy", "--first", "o"]));
+    }
+
+    #[test]
+    fn let_the_computer_play_against_itself() {
+        assert_eq!(
+            Config::from_iter(&["", "selfplay", "-x", "easy", "--rounds", "100000"]).command(),
+            Command::SelfPlay(SelfPlay {
+                x: Difficulty::Easy,
+                o: Difficulty::Hard,
+                first: Mark::X,
+                rounds: 100000,
+                draw: DrawRule::FullGrid,
+                variant: Variant::Standard,
+                seed: None
+            })
+        );
+
+        assert!(Config::from_iter_safe(&["", "selfplay", "-x", "human"]).is_err());
+    }
+
+    #[test]
+    fn analyze_or_solve_a_position() {
+        assert_eq!(
+            Config::from_iter(&["", "analyze", "x.o/.x./...", "o"]).command(),
+            Command::Analyze { json: false, variant: Variant::Standard, position: vec![String::from("x.o/.x./..."), String::from("o")] }
+        );
+
+        assert_eq!(
+            Config::from_iter(&["", "analyze", "--json", "x.o/.x./... o"]).command(),
+            Command::Analyze { json: true, variant: Variant::Standard, position: vec![String::from("x.o/.x./... o")] }
+        );
+
+        assert_eq!(
+            Config::from_iter(&["", "solve", "--variant", "misere", "x.o/.x./... o"]).command(),
+            Command::Solve { variant: Variant::Misere, position: vec![String::from("x.o/.x./... o")] }
+        );
+
+        assert!(Config::from_iter_safe(&["", "solve"]).is_err());
+    }
+
+    #[test]
+    fn a_position_has_to_be_small_enough_to_solve() {
+        let position = |args: &[&str]| read_position(&args.iter().map(|&arg| String::from(arg)).collect::<Vec<_>>());
+
+        assert!(position(&["x.o/.x./...", "o"]).is_ok());
+        assert!(matches!(position(&["x.o/.x./... z"]), Err(e) if e.starts_with("Invalid position")));
+        assert!(matches!(position(&["...../...../...../...../..... x"]), Err(e) if e.contains("too large")));
+    }
+
+    #[test]
+    fn notakto_is_described_in_the_help() {
+        let mut help = Vec::new();
+
+        Config::clap().write_long_help(&mut help).unwrap();
+
+        let help = String::from_utf8(help).unwrap();
+
+        assert!(help.contains("notakto     Plays Notakto, where both players play x's"));
+        assert!(!help.contains("only need to know"));
+
+        let help = Config::from_iter_safe(&["", "notakto", "--help"]).unwrap_err().message;
+
+        assert!(help.contains("Plays Notakto, where both players play x's"));
+    }
+
+    #[test]
+    fn play_ultimate_tic_tac_toe() {
+        assert_eq!(
+            Config::from_iter(&["", "ultimate", "-x", "medium", "--first", "o"]).command(),
+            Command::Ultimate(Versus {
+                x: Player::Computer(Difficulty::Medium),
+                o: Player::Computer(Difficulty::Hard),
+                first: Mark::O,
+                seed: None
+            })
+        );
+    }
+
+    #[test]
+    fn play_qubic() {
+        assert_eq!(
+            Config::from_iter(&["", "qubic", "-o", "human", "--seed", "7"]).command(),
+            Command::Qubic(Versus {
+                x: Player::Human,
+                o: Player::Human,
                 first: Mark::X,
-                rounds: 50
+                seed: Some(7)
+            })
+        );
+    }
+
+    #[test]
+    fn play_wild_tic_tac_toe() {
+        assert_eq!(
+            Config::from_iter(&["", "wild", "-x", "easy", "-o", "easy"]).command(),
+            Command::Wild(Versus {
+                x: Player::Computer(Difficulty::Easy),
+                o: Player::Computer(Difficulty::Easy),
+                first: Mark::X,
+                seed: None
+            })
+        );
+    }
+
+    #[test]
+    fn play_notakto() {
+        assert_eq!(
+            Config::from_iter(&["", "notakto", "-b", "2", "-o", "medium"]).command(),
+            Command::Notakto {
+                versus: Versus {
+                    x: Player::Human,
+                    o: Player::Computer(Difficulty::Medium),
+                    first: Mark::X,
+                    seed: None
+                },
+                boards: 2
             }
         );
+
+        assert!(Config::from_iter_safe(&["", "notakto", "--boards", "5"]).is_err());
+        assert!(Config::from_iter_safe(&["", "notakto", "--boards", "0"]).is_err());
+    }
+
+    #[test]
+    fn replay_a_saved_game() {
+        assert_eq!(
+            Config::from_iter(&["", "replay", "game.txt"]).command(),
+            Command::Replay { file: PathBuf::from("game.txt") }
+        );
     }
 }
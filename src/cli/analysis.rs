@@ -0,0 +1,216 @@
+use std::io::Write;
+
+use super::interactive::format_position;
+use crate::ai::{ self, Evaluation, Value };
+use crate::{ Game, Mark, Outcome, Position };
+
+/// What the computer thinks of a position that's in play.
+struct Analysis {
+    evaluations: Vec<(Position, Evaluation)>,
+    best: Vec<Position>,
+    variation: Vec<Position>
+}
+
+impl Analysis {
+    fn of(game: &Game) -> Option<Self> {
+        if game.is_game_over() {
+            return None;
+        }
+
+        let evaluations = ai::evaluate_moves(game);
+        let top = evaluations.iter().map(|&(_, e)| rank(e)).max().unwrap();
+        let best = evaluations.iter().filter(|&&(_, e)| rank(e) == top).map(|&(pos, _)| pos).collect();
+        let variation = ai::principal_variation(game);
+
+        Some(Self { evaluations, best, variation })
+    }
+
+    /// The evaluation of the position for the player whose turn it is, which is that of their
+    /// best move.
+    fn evaluation(&self) -> Evaluation {
+        let best = self.best[0];
+
+        self.evaluations.iter().find(|&&(pos, _)| pos == best).unwrap().1
+    }
+}
+
+/// Prints what the computer thinks of every move that can be played in the `game`, which moves
+/// are the best and how the game goes on when both players play their best moves.
+///
+/// It's printed as text unless `json` is set. The JSON is an object with the `position` in its
+/// notation, the `turn`, the `outcome` (`null`, `"x"`, `"o"` or `"draw"`), the `value` and `plies`
+/// of the position for the player whose turn it is, the `moves` with their `position`, `value` and
+/// `plies`, the `best` positions and the `principal_variation`. Positions are 0-based `[r, c]`
+/// pairs, as in the library.
+pub fn analyze(game: &Game, json: bool) {
+    if json {
+        write_json(&mut std::io::stdout(), game);
+    } else {
+        write_analysis(&mut std::io::stdout(), game);
+    }
+}
+
+/// Prints the result that the player whose turn it is in the `game` can force, and how long it
+/// takes to get there.
+pub fn solve(game: &Game) {
+    write_solution(&mut std::io::stdout(), game);
+}
+
+fn write_analysis(out: &mut impl Write, game: &Game) {
+    let analysis = match Analysis::of(game) {
+        Some(analysis) => analysis,
+        None => return write_outcome(out, game)
+    };
+
+    writeln!(out, "{} {}\n", game.turn(), analysis.evaluation()).unwrap();
+
+    for &(pos, evaluation) in &analysis.evaluations {
+        writeln!(out, "{} {}", format_position(pos), evaluation).unwrap();
+    }
+
+    let best = analysis.best.iter().map(|&pos| format_position(pos)).collect::<Vec<_>>();
+    let variation = marks(game.turn())
+        .zip(&analysis.variation)
+        .map(|(mark, &pos)| format!("{} {}", mark, format_position(pos)))
+        .collect::<Vec<_>>();
+
+    writeln!(out, "\nBest: {}", best.join(", ")).unwrap();
+    writeln!(out, "Principal variation: {}", variation.join(", ")).unwrap();
+}
+
+fn write_json(out: &mut impl Write, game: &Game) {
+    let outcome = match game.outcome() {
+        Some(Outcome::Win(winner)) => format!("\"{}\"", winner),
+        Some(Outcome::Draw) => String::from("\"draw\""),
+        None => String::from("null")
+    };
+
+    write!(out, "{{\"position\":\"{}\",\"turn\":\"{}\",\"outcome\":{}", game, game.turn(), outcome).unwrap();
+
+    match Analysis::of(game) {
+        Some(analysis) => {
+            let evaluation = analysis.evaluation();
+            let moves = analysis.evaluations
+                .iter()
+                .map(|&(pos, e)| format!("{{\"position\":{},\"value\":{},\"plies\":{}}}", json_position(pos), json_value(e.value), e.plies))
+                .collect::<Vec<_>>();
+
+            write!(out, ",\"value\":{},\"plies\":{}", json_value(evaluation.value), evaluation.plies).unwrap();
+            write!(out, ",\"moves\":[{}]", moves.join(",")).unwrap();
+            write!(out, ",\"best\":{}", json_positions(&analysis.best)).unwrap();
+            write!(out, ",\"principal_variation\":{}", json_positions(&analysis.variation)).unwrap();
+        },
+        None => write!(out, ",\"value\":null,\"plies\":null,\"moves\":[],\"best\":[],\"principal_variation\":[]").unwrap()
+    }
+
+    writeln!(out, "}}").unwrap();
+}
+
+fn write_solution(out: &mut impl Write, game: &Game) {
+    match Analysis::of(game) {
+        Some(analysis) => writeln!(out, "{} {}", game.turn(), analysis.evaluation()).unwrap(),
+        None => write_outcome(out, game)
+    }
+}
+
+fn write_outcome(out: &mut impl Write, game: &Game) {
+    match game.outcome() {
+        Some(Outcome::Win(winner)) => writeln!(out, "The game is over, {} won", winner).unwrap(),
+        Some(Outcome::Draw) => writeln!(out, "The game is over, it was drawn").unwrap(),
+        None => unreachable!()
+    }
+}
+
+/// The marks of the players in turn, starting with `first`.
+fn marks(first: Mark) -> impl Iterator<Item = Mark> {
+    std::iter::successors(Some(first), |mark| Some(mark.swap()))
+}
+
+fn json_position((r, c): Position) -> String {
+    format!("[{},{}]", r, c)
+}
+
+fn json_positions(positions: &[Position]) -> String {
+    format!("[{}]", positions.iter().map(|&pos| json_position(pos)).collect::<Vec<_>>().join(","))
+}
+
+fn json_value(value: Value) -> &'static str {
+    match value {
+        Value::Win => "\"win\"",
+        Value::Draw => "\"draw\"",
+        Value::Loss => "\"loss\""
+    }
+}
+
+/// Orders evaluations from worst to best, so that among moves with the same value the quickest
+/// wins and the slowest losses come out on top.
+pub(super) fn rank(evaluation: Evaluation) -> (Value, isize) {
+    let plies = evaluation.plies as isize;
+
+    match evaluation.value {
+        Value::Win => (Value::Win, -plies),
+        Value::Draw => (Value::Draw, 0),
+        Value::Loss => (Value::Loss, plies)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn output(write: fn(&mut Vec<u8>, &Game), position: &str) -> String {
+        let mut out = Vec::new();
+
+        write(&mut out, &position.parse::<Game>().unwrap());
+
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn every_move_is_analyzed() {
+        assert_eq!(output(write_analysis, "xo./xo./... x"), "\
+x wins in 1
+
+(1, 3) loses in 2
+(2, 3) loses in 2
+(3, 1) wins in 1
+(3, 2) draws in 5
+(3, 3) loses in 2
+
+Best: (3, 1)
+Principal variation: x (3, 1)
+");
+
+        assert_eq!(output(write_analysis, "xo./xo./x.. x"), "The game is over, x won\n");
+    }
+
+    #[test]
+    fn the_analysis_as_json() {
+        assert_eq!(
+            output(write_json, "xo./xo./... x"),
+            concat!(
+                r#"{"position":"xo./xo./... x","turn":"x","outcome":null,"value":"win","plies":1,"moves":["#,
+                r#"{"position":[0,2],"value":"loss","plies":2},{"position":[1,2],"value":"loss","plies":2},"#,
+                r#"{"position":[2,0],"value":"win","plies":1},{"position":[2,1],"value":"draw","plies":5},"#,
+                r#"{"position":[2,2],"value":"loss","plies":2}],"best":[[2,0]],"principal_variation":[[2,0]]}"#,
+                "\n"
+            )
+        );
+
+        assert_eq!(
+            output(write_json, "xo./xo./x.. x"),
+            concat!(
+                r#"{"position":"xo./xo./x.. x","turn":"x","outcome":"x","value":null,"plies":null,"#,
+                r#""moves":[],"best":[],"principal_variation":[]}"#,
+                "\n"
+            )
+        );
+    }
+
+    #[test]
+    fn positions_are_solved() {
+        assert_eq!(output(write_solution, "x.o/.x./... o"), "o draws in 6\n");
+        assert_eq!(output(write_solution, "xo./xo./... x"), "x wins in 1\n");
+        assert_eq!(output(write_solution, "xo./xo./x.. x"), "The game is over, x won\n");
+    }
+}
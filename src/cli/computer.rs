@@ -0,0 +1,292 @@
+use std::error::Error;
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{ Rng, SeedableRng };
+
+use super::interactive::{ format_turn, read_continue, read_input };
+use super::Player;
+use crate::{ Difficulty, Mark, MoveOutcome, Outcome };
+
+/// A game where the moves played so far, along with the players who played them, can be taken
+/// back one by one.
+pub(super) trait History {
+    type Move: Copy;
+
+    fn history(&self) -> &[(Self::Move, Mark)];
+
+    fn undo(&mut self) -> Option<(Self::Move, Mark)>;
+}
+
+/// A game that's too big to solve, so the computer plays it by searching a number of moves ahead.
+///
+/// Any such game can be played with [`run`], given how to read a move and how to show the game.
+///
+/// [`run`]: ./fn.run.html
+pub(super) trait Searchable: History {
+    /// How many moves ahead the computer looks when playing at `Difficulty::Medium`.
+    const MEDIUM_DEPTH: usize;
+
+    /// How many moves ahead the computer looks when playing at `Difficulty::Hard`.
+    const HARD_DEPTH: usize;
+
+    /// The lines that welcome the players and explain the rules.
+    const INTRO: &'static [&'static str];
+
+    /// The lines that explain how to enter a move, shown when the input isn't one.
+    const MOVE_FORMAT: &'static [&'static str];
+
+    /// Why a move can't be played, which is shown to a human who tries to play it.
+    type Error: Error;
+
+    fn legal_moves(&self) -> Vec<Self::Move>;
+
+    /// Returns the equally-best moves when looking `depth` moves ahead.
+    fn best_moves(&self, depth: usize) -> Vec<Self::Move>;
+
+    fn play(&mut self, mv: Self::Move) -> Result<MoveOutcome, Self::Error>;
+
+    fn restart(&mut self);
+
+    fn turn(&self) -> Mark;
+
+    fn outcome(&self) -> Option<Outcome>;
+
+    fn parse_move(s: &str) -> Option<Self::Move>;
+
+    /// Formats a move as it follows "The computer played".
+    fn format_move(mv: Self::Move) -> String;
+
+    /// Formats the game as it's shown to a human whose turn it is, and once it's over.
+    fn format_game(&self) -> String;
+}
+
+/// Plays games between humans and computers, starting with the given `game`, for as long as the
+/// humans want to keep playing. When both sides are played by the computer a single game is
+/// played.
+pub(super) fn run<G: Searchable>(mut game: G, x: Player, o: Player, seed: Option<u64>) {
+    println!("{}", format_intro::<G>());
+
+    let mut players = Players::new(x, o, seed);
+
+    loop {
+        play_one_game(&mut game, &mut players);
+
+        if players.humans > 0 && read_continue() {
+            game.restart();
+        } else {
+            break;
+        }
+    }
+}
+
+fn play_one_game<G: Searchable>(game: &mut G, players: &mut Players) {
+    while game.outcome().is_none() {
+        play_one_turn(game, players);
+    }
+
+    println!("{}", players.format_outcome(game.outcome().unwrap()));
+    println!("{}", game.format_game());
+}
+
+fn play_one_turn<G: Searchable>(game: &mut G, players: &mut Players) {
+    match players.computer(game.turn()) {
+        Some(computer) => {
+            let mv = computer.choose(game);
+
+            game.play(mv).unwrap();
+
+            println!("The computer played {}", G::format_move(mv));
+        },
+        None => {
+            println!("{}", format_turn(players.humans, game.turn()));
+            println!("{}", game.format_game());
+
+            loop {
+                match read_command::<G>() {
+                    Command::Play(mv) => {
+                        match game.play(mv) {
+                            Ok(_) => break,
+                            Err(error) => println!("Try again, {}", error)
+                        }
+                    },
+                    Command::Undo => {
+                        if players.undo(game) {
+                            break;
+                        } else {
+                            println!("There's nothing to undo");
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+enum Command<M> {
+    Play(M),
+    Undo
+}
+
+fn read_command<G: Searchable>() -> Command<G::Move> {
+    let input = read_input("> ");
+
+    if input.eq_ignore_ascii_case("undo") {
+        return Command::Undo;
+    }
+
+    match G::parse_move(&input) {
+        Some(mv) => Command::Play(mv),
+        None => {
+            for line in G::MOVE_FORMAT {
+                println!("{}", line);
+            }
+
+            println!("or enter \"undo\" to take back your last move");
+
+            read_command::<G>()
+        }
+    }
+}
+
+fn format_intro<G: Searchable>() -> String {
+    let instructions = ["Enter \"undo\" to take back your last move", "Press Ctrl-C to exit at any time"];
+
+    format!("{}\n\n", G::INTRO.iter().chain(&instructions).copied().collect::<Vec<_>>().join("\n"))
+}
+
+/// Chooses the computer's moves at a given [`Difficulty`].
+///
+/// [`Difficulty`]: ../../enum.Difficulty.html
+pub(super) struct Computer {
+    difficulty: Difficulty,
+    rng: StdRng
+}
+
+impl Computer {
+    fn new(player: Player, seed: Option<u64>) -> Option<Self> {
+        let rng = match seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy()
+        };
+
+        match player {
+            Player::Human => None,
+            Player::Computer(difficulty) => Some(Self { difficulty, rng })
+        }
+    }
+
+    pub(super) fn choose<G: Searchable>(&mut self, game: &G) -> G::Move {
+        let depth = match self.difficulty {
+            Difficulty::Easy => None,
+            Difficulty::Medium => Some(G::MEDIUM_DEPTH),
+            Difficulty::Hard => Some(G::HARD_DEPTH),
+            Difficulty::Blunder(epsilon) if self.rng.gen_bool(epsilon) => None,
+            Difficulty::Blunder(_) => Some(G::HARD_DEPTH)
+        };
+
+        let moves = match depth {
+            Some(depth) => game.best_moves(depth),
+            None => game.legal_moves()
+        };
+
+        *moves.choose(&mut self.rng).unwrap()
+    }
+}
+
+/// The computer players, where a missing computer means that side is played by a human.
+///
+/// A computer is a [`Computer`] by default, but the Tic-tac-toe CLI plays with a [`Strategy`].
+///
+/// [`Computer`]: ./struct.Computer.html
+/// [`Strategy`]: ../../trait.Strategy.html
+pub(super) struct Players<C = Computer> {
+    x: Option<C>,
+    o: Option<C>,
+    pub(super) humans: u32
+}
+
+impl Players {
+    pub(super) fn new(x: Player, o: Player, seed: Option<u64>) -> Self {
+        let (x_seed, o_seed) = super::seeds(seed);
+
+        Self::with_computers(Computer::new(x, x_seed), Computer::new(o, o_seed))
+    }
+}
+
+impl<C> Players<C> {
+    pub(super) fn with_computers(x: Option<C>, o: Option<C>) -> Self {
+        let humans = x.is_none() as u32 + o.is_none() as u32;
+
+        Self { x, o, humans }
+    }
+
+    pub(super) fn computer(&mut self, mark: Mark) -> Option<&mut C> {
+        match mark {
+            Mark::X => self.x.as_mut(),
+            Mark::O => self.o.as_mut()
+        }
+    }
+
+    pub(super) fn is_human(&self, mark: Mark) -> bool {
+        match mark {
+            Mark::X => self.x.is_none(),
+            Mark::O => self.o.is_none()
+        }
+    }
+
+    /// Takes back moves up to and including the last move made by a human, so that against the
+    /// computer both the human's last move and the computer's reply are taken back.
+    ///
+    /// It returns `false`, and takes nothing back, if no human has made a move.
+    pub(super) fn undo<G: History>(&self, game: &mut G) -> bool {
+        let is_human = |&(_, mark): &(G::Move, Mark)| self.is_human(mark);
+
+        if game.history().iter().any(is_human) {
+            while let Some(last) = game.undo() {
+                if is_human(&last) {
+                    break;
+                }
+            }
+
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Formats the message that announces the `outcome` of a game.
+    pub(super) fn format_outcome(&self, outcome: Outcome) -> String {
+        match outcome {
+            Outcome::Win(winner) => match (self.is_human(winner), self.humans) {
+                (true, 2) => format!("Congratulations! {} won.", winner),
+                (true, _) => String::from("Congratulations! You won."),
+                (false, 1) => String::from("The computer won. Better luck next time."),
+                (false, _) => format!("{} won.", winner)
+            },
+            Outcome::Draw => String::from("Game drawn.")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_outcome_is_announced_to_the_humans() {
+        let players = Players::new(Player::Human, Player::Computer(Difficulty::Hard), None);
+
+        assert_eq!(players.format_outcome(Outcome::Win(Mark::X)), "Congratulations! You won.");
+        assert_eq!(players.format_outcome(Outcome::Win(Mark::O)), "The computer won. Better luck next time.");
+
+        let players = Players::new(Player::Human, Player::Human, None);
+
+        assert_eq!(players.format_outcome(Outcome::Win(Mark::O)), "Congratulations! o won.");
+
+        let players = Players::new(Player::Computer(Difficulty::Easy), Player::Computer(Difficulty::Hard), None);
+
+        assert_eq!(players.format_outcome(Outcome::Win(Mark::O)), "o won.");
+        assert_eq!(players.format_outcome(Outcome::Draw), "Game drawn.");
+    }
+}
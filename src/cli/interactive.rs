@@ -1,19 +1,25 @@
+use std::fs;
 use std::io::Write;
 
-use crate::cli::Player;
-use crate::{ ai, Cell, Game, Grid, Mark, Outcome, PlayError, Position };
+use super::computer::{ History, Players };
+use crate::{ Cell, Game, Grid, Mark, Outcome, Position, Record, Strategy, Variant };
 
-pub fn run(first: Mark, x: Player, o: Player) {
-    println!("{}", format_intro());
+/// Plays games between humans and computers, starting with the given `game`, for as long as the
+/// humans want to keep playing.
+///
+/// A side is played by a human if its strategy is `None`, otherwise the computer uses the
+/// given [`Strategy`] to choose its moves. At least one side must be played by a human.
+///
+/// [`Strategy`]: ../../trait.Strategy.html
+pub fn run(mut game: Game, x: Option<Box<dyn Strategy>>, o: Option<Box<dyn Strategy>>) {
+    println!("{}", format_intro(game.variant()));
 
-    let mut game = Game::start(first);
-    let humans = Player::count_humans(&[x, o]);
+    let mut players = Players::with_computers(x, o);
+
+    assert!(players.humans > 0, "at least one side must be played by a human");
 
     loop {
-        match game.turn() {
-            Mark::X => play_one_game(&mut game, humans, x, o),
-            Mark::O => play_one_game(&mut game, humans, o, x)
-        }
+        play_one_game(&mut game, &mut players);
 
         if read_continue() {
             game.restart();
@@ -23,67 +29,91 @@ pub fn run(first: Mark, x: Player, o: Player) {
     }
 }
 
-fn play_one_game(game: &mut Game, humans: u32, first: Player, second: Player) {
-    let mut current = first;
-    let mut next = second;
-
-    loop {
-        play_one_turn(game, humans, current);
-
-        match game.outcome() {
-            None => std::mem::swap(&mut current, &mut next),
-            Some(outcome) => {
-                handle_game_over(outcome, current, humans, game);
-                break;
-            }
-        }
+fn play_one_game(game: &mut Game, players: &mut Players<Box<dyn Strategy>>) {
+    // A loaded game may already be over
+    while game.is_playing() {
+        play_one_turn(game, players);
     }
+
+    handle_game_over(game.outcome().unwrap(), players, game);
 }
 
-fn play_one_turn(game: &mut Game, humans: u32, current: Player) {
-    match current {
-        Player::Human => {
-            println!("{}", format_turn(humans, game.turn()));
+fn play_one_turn(game: &mut Game, players: &mut Players<Box<dyn Strategy>>) {
+    match players.computer(game.turn()) {
+        Some(strategy) => {
+            let pos = strategy.choose(game);
+
+            game.play(pos).unwrap();
+
+            println!("The computer played at {}", format_position(pos));
+        },
+        None => {
+            println!("{}", format_turn(players.humans, game.turn()));
             println!("{}", format_grid(game.grid()));
 
             loop {
-                let pos = read_position(game.grid(), true);
-
-                if let Some(error) = game.play(pos) {
-                    match error {
-                        PlayError::OutOfBounds => println!("Try again, that position is out of bounds"),
-                        PlayError::AlreadyMarked => println!("Try again, that position is already taken")
-                    }
-                } else {
-                    break;
+                match read_command(game.grid(), true) {
+                    Command::Play(pos) => {
+                        match game.play(pos) {
+                            Ok(_) => break,
+                            Err(error) => println!("Try again, {}", error)
+                        }
+                    },
+                    Command::Undo => {
+                        if players.undo(game) {
+                            break;
+                        } else {
+                            println!("There's nothing to undo");
+                        }
+                    },
+                    Command::Save(path) => save(game, players, &path)
                 }
             }
-        },
-        Player::Computer => {
-            let pos = ai::random_move(game);
+        }
+    }
+}
 
-            game.play(pos);
+fn save(game: &Game, players: &Players<Box<dyn Strategy>>, path: &str) {
+    let name = |mark| if players.is_human(mark) { "Human" } else { "Computer" };
+    let record = Record::new(game.clone()).with_players(name(Mark::X), name(Mark::O));
 
-            println!("The computer played at {}", format_position(pos));
-        }
+    match fs::write(path, record.to_string()) {
+        Ok(()) => println!("Saved the game to {}", path),
+        Err(error) => println!("Couldn't save the game to {}, {}", path, error)
     }
 }
 
-fn handle_game_over(outcome: Outcome, player: Player, humans: u32, game: &Game) {
-    match (outcome, player, humans) {
-        (Outcome::Win, Player::Human, 2) => println!("Congratulations! {} won.", game.turn()),
-        (Outcome::Win, Player::Human, 1) => println!("Congratulations! You won."),
-        (Outcome::Win, Player::Computer, 1) => println!("The computer won. Better luck next time."),
-        (Outcome::Draw, _, _) => println!("Game drawn."),
-        _ => unreachable!()
+impl History for Game {
+    type Move = Position;
+
+    fn history(&self) -> &[(Position, Mark)] {
+        self.history()
+    }
+
+    fn undo(&mut self) -> Option<(Position, Mark)> {
+        self.undo()
+    }
+}
+
+fn handle_game_over(outcome: Outcome, players: &Players<Box<dyn Strategy>>, game: &Game) {
+    if let (Outcome::Win(winner), Variant::Misere) = (outcome, game.variant()) {
+        let loser = winner.swap();
+
+        match (players.is_human(loser), players.humans) {
+            (true, 2) => println!("{} completed a line.", loser),
+            (true, 1) => println!("You completed a line."),
+            (false, 1) => println!("The computer completed a line."),
+            _ => unreachable!()
+        }
     }
 
-    println!("{}", format_grid(game.grid()));
+    println!("{}", players.format_outcome(outcome));
+    println!("{}", format_winning_grid(game.grid(), &game.winning_lines().concat()));
 }
 
 // INPUT
 
-fn read_continue() -> bool {
+pub(super) fn read_continue() -> bool {
     let input = read_input("Do you want to continue playing? (Y/n) ");
 
     match input.to_ascii_lowercase().as_ref() {
@@ -93,27 +123,44 @@ fn read_continue() -> bool {
     }
 }
 
-fn read_position(grid: &Grid, show_hint: bool) -> Position {
+enum Command {
+    Play(Position),
+    Undo,
+    Save(String)
+}
+
+fn read_command(grid: &Grid, show_hint: bool) -> Command {
     let input = read_input("> ");
 
+    if input.eq_ignore_ascii_case("undo") {
+        return Command::Undo;
+    }
+
+    if let Some(path) = input.strip_prefix("save ") {
+        return Command::Save(path.trim().to_owned());
+    }
+
     match parse_position(&input) {
-        Some(pos) => pos,
+        Some(pos) => Command::Play(pos),
         None => {
             if show_hint {
                 let (r, c) = first_unmarked_position(grid);
+                let shape = grid.shape();
 
                 println!("Try again, but this time enter a position in the format \"r c\",");
-                println!("where 1 <= r <= 3 and 1 <= c <= 3, for e.g. \"{} {}\"", r + 1, c + 1);
+                println!("where 1 <= r <= {} and 1 <= c <= {}, for e.g. \"{} {}\"", shape.rows(), shape.columns(), r + 1, c + 1);
+                println!("or enter \"undo\" to take back your last move");
+                println!("or enter \"save <file>\" to save the game");
 
-                read_position(grid, false)
+                read_command(grid, false)
             } else {
-                read_position(grid, show_hint)
+                read_command(grid, show_hint)
             }
         }
     }
 }
 
-fn parse_position(s: &str) -> Option<Position> {
+pub(super) fn parse_position(s: &str) -> Option<Position> {
     let parts = s.split_ascii_whitespace().collect::<Vec<_>>();
 
     match &parts[..] {
@@ -129,7 +176,7 @@ fn first_unmarked_position(grid: &Grid) -> Position {
     grid.unmarked_positions().next().unwrap()
 }
 
-fn read_input(prompt: &str) -> String {
+pub(super) fn read_input(prompt: &str) -> String {
     print!("{}", prompt);
     std::io::stdout().flush().unwrap();
 
@@ -146,15 +193,22 @@ fn read_line(buffer: &mut String) {
 
 // OUTPUT
 
-fn format_intro() -> String {
-    format!("{}\n{}\n{}\n\n",
-        "Welcome to Tic-tac-toe",
+fn format_intro(variant: Variant) -> String {
+    let welcome = match variant {
+        Variant::Standard => "Welcome to Tic-tac-toe",
+        Variant::Misere => "Welcome to misère Tic-tac-toe, where completing a line loses"
+    };
+
+    format!("{}\n{}\n{}\n{}\n{}\n\n",
+        welcome,
         "Play as many games as you want",
+        "Enter \"undo\" to take back your last move",
+        "Enter \"save <file>\" to save the game",
         "Press Ctrl-C to exit at any time"
     )
 }
 
-fn format_turn(humans: u32, mark: Mark) -> String {
+pub(super) fn format_turn(humans: u32, mark: Mark) -> String {
     if humans == 2 {
         format!("{}'s turn", mark)
     } else {
@@ -162,30 +216,35 @@ fn format_turn(humans: u32, mark: Mark) -> String {
     }
 }
 
-fn format_grid(grid: &Grid) -> String {
-    let cells = grid.cells().collect::<Vec<_>>();
-    let sep = "---+---+---";
-
-    format!("{}\n{}\n{}\n{}\n{}",
-        format_row(cells[0], cells[1], cells[2]),
-        sep,
-        format_row(cells[3], cells[4], cells[5]),
-        sep,
-        format_row(cells[6], cells[7], cells[8])
-    )
+pub(super) fn format_grid(grid: &Grid) -> String {
+    format_winning_grid(grid, &[])
 }
 
-fn format_row(a: &Cell, b: &Cell, c: &Cell) -> String {
-    format!(" {} | {} | {}", format_cell(a), format_cell(b), format_cell(c))
+/// Formats the grid with the marks at the winning positions in uppercase.
+pub(super) fn format_winning_grid(grid: &Grid, winning: &[Position]) -> String {
+    let shape = grid.shape();
+    let sep = vec!["---"; shape.columns()].join("+");
+
+    (0..shape.rows())
+        .map(|r| {
+            let cells = (0..shape.columns())
+                .map(|c| format_cell(&grid.cell_at((r, c)), winning.contains(&(r, c))))
+                .collect::<Vec<_>>();
+
+            format!(" {}", cells.join(" | "))
+        })
+        .collect::<Vec<_>>()
+        .join(&format!("\n{}\n", sep))
 }
 
-fn format_cell(cell: &Cell) -> String {
+fn format_cell(cell: &Cell, is_winning: bool) -> String {
     match cell {
+        Some(mark) if is_winning => mark.to_string().to_uppercase(),
         Some(mark) => mark.to_string(),
         None => String::from(" ")
     }
 }
 
-fn format_position((r, c): Position) -> String {
+pub(super) fn format_position((r, c): Position) -> String {
     format!("({}, {})", r + 1, c + 1)
 }
@@ -1,35 +1,198 @@
+use std::fmt;
 use std::io::Write;
 
-use crate::{ ai, Game, Mark, Outcome };
+use crate::{ Game, Mark, Outcome, Strategy };
 
-pub fn run(first: Mark, rounds: u8) {
-    let mut game = Game::start(first);
+/// Plays `rounds` games between two computers, starting with the given `game`, printing the
+/// winner of each game as `x` or `o` and each draw as `.`, followed by a summary of the games.
+pub fn run(mut game: Game, rounds: u32, mut x: Box<dyn Strategy>, mut o: Box<dyn Strategy>) {
+    play(&mut std::io::stdout(), &mut game, rounds, x.as_mut(), o.as_mut());
+}
+
+/// The statistics of a run of games.
+#[derive(Debug, Default, PartialEq, Clone)]
+struct Summary {
+    games: u32,
+    x_wins: u32,
+    o_wins: u32,
+    draws: u32,
+    first_mover_wins: u32,
+    second_mover_wins: u32,
+    moves: u64
+}
+
+impl Summary {
+    /// Adds a finished `game`, whose first `start` moves were played before the run, and in which
+    /// `first` moved first.
+    fn add(&mut self, game: &Game, first: Mark, start: usize) {
+        let outcome = game.outcome().unwrap();
+
+        self.games += 1;
+        self.moves += (game.moves_played() - start) as u64;
+
+        match outcome {
+            Outcome::Win(Mark::X) => self.x_wins += 1,
+            Outcome::Win(Mark::O) => self.o_wins += 1,
+            Outcome::Draw => self.draws += 1
+        }
+
+        match outcome {
+            Outcome::Win(winner) if winner == first => self.first_mover_wins += 1,
+            Outcome::Win(_) => self.second_mover_wins += 1,
+            Outcome::Draw => ()
+        }
+    }
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let percent = |n: u32| 100.0 * n as f64 / self.games as f64;
+        let rate = |n: u32| {
+            let (low, high) = wilson_interval(n, self.games);
+
+            format!("{} ({:.1}%, 95% CI {:.1}%-{:.1}%)", n, percent(n), 100.0 * low, 100.0 * high)
+        };
+
+        writeln!(f, "Games: {}", self.games)?;
+        writeln!(f, "x won: {}", rate(self.x_wins))?;
+        writeln!(f, "o won: {}", rate(self.o_wins))?;
+        writeln!(f, "Drawn: {}", rate(self.draws))?;
+        writeln!(f, "Average length: {:.1} moves", self.moves as f64 / self.games as f64)?;
+        write!(
+            f,
+            "First mover advantage: {:+.1} points (first mover won {}, second mover won {})",
+            percent(self.first_mover_wins) - percent(self.second_mover_wins),
+            self.first_mover_wins,
+            self.second_mover_wins
+        )
+    }
+}
+
+fn play(out: &mut impl Write, game: &mut Game, rounds: u32, x: &mut dyn Strategy, o: &mut dyn Strategy) {
+    let mut summary = Summary::default();
 
     for _ in 0..rounds {
-        play_one_round(&mut game);
+        play_one_round(out, game, x, o, &mut summary);
     }
 
     if rounds > 0 {
-        println!("");
+        writeln!(out, "\n\n{}", summary).unwrap();
     }
 }
 
-fn play_one_round(game: &mut Game) {
-    loop {
-        game.play(ai::random_move(game));
+fn play_one_round(out: &mut impl Write, game: &mut Game, x: &mut dyn Strategy, o: &mut dyn Strategy, summary: &mut Summary) {
+    let first = game.turn();
+    let start = game.moves_played();
 
-        if let Some(outcome) = game.outcome() {
-            handle_game_over(outcome, game.turn());
-            game.restart();
-            break;
-        }
+    // A loaded game may already be over
+    while game.is_playing() {
+        let pos = match game.turn() {
+            Mark::X => x.choose(game),
+            Mark::O => o.choose(game)
+        };
+
+        game.play(pos).unwrap();
     }
+
+    summary.add(game, first, start);
+    handle_game_over(out, game.outcome().unwrap());
+    game.restart();
 }
 
-fn handle_game_over(outcome: Outcome, winner: Mark) {
+fn handle_game_over(out: &mut impl Write, outcome: Outcome) {
     match outcome {
-        Outcome::Win => print!("{}", winner),
-        Outcome::Draw => print!(".")
+        Outcome::Win(winner) => write!(out, "{}", winner).unwrap(),
+        Outcome::Draw => write!(out, ".").unwrap()
+    }
+    out.flush().unwrap();
+}
+
+/// Returns the [Wilson score interval] at 95% confidence for a proportion of `n` out of `total`.
+///
+/// [Wilson score interval]: https://en.wikipedia.org/wiki/Binomial_proportion_confidence_interval#Wilson_score_interval
+fn wilson_interval(n: u32, total: u32) -> (f64, f64) {
+    const Z: f64 = 1.96;
+
+    let total = total as f64;
+    let p = n as f64 / total;
+    let denominator = 1.0 + Z * Z / total;
+    let center = (p + Z * Z / (2.0 * total)) / denominator;
+    let margin = Z / denominator * (p * (1.0 - p) / total + Z * Z / (4.0 * total * total)).sqrt();
+
+    // Rounding can leave the bounds just outside [0, 1] when n is 0 or total
+    ((center - margin).max(0.0), (center + margin).min(1.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ Difficulty, FirstUnmarked };
+
+    fn self_play(x: Difficulty, o: Difficulty, seed: u64) -> String {
+        let mut out = Vec::new();
+        let mut x = x.seeded_strategy(seed);
+        let mut o = o.seeded_strategy(seed + 1);
+
+        let mut game = Game::start(Mark::X);
+
+        play(&mut out, &mut game, 25, x.as_mut(), o.as_mut());
+
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn the_same_seed_plays_the_same_games() {
+        let output = self_play(Difficulty::Easy, Difficulty::Medium, 42);
+
+        assert_eq!(output.lines().next().unwrap().len(), 25);
+        assert_eq!(self_play(Difficulty::Easy, Difficulty::Medium, 42), output);
+        assert_ne!(self_play(Difficulty::Easy, Difficulty::Medium, 43), output);
+    }
+
+    #[test]
+    fn the_same_seed_plays_the_same_games_at_hard() {
+        let output = self_play(Difficulty::Hard, Difficulty::Hard, 7);
+        let games = output.lines().next().unwrap();
+
+        // Perfect players only ever draw each other
+        assert_eq!(games, ".".repeat(25));
+        assert_eq!(self_play(Difficulty::Hard, Difficulty::Hard, 7), output);
+    }
+
+    #[test]
+    fn a_summary_follows_the_games() {
+        let mut out = Vec::new();
+        let mut game = Game::start(Mark::X);
+
+        // X wins on the anti-diagonal, (0, 2) to (2, 0), every time, and so keeps moving first
+        play(&mut out, &mut game, 300, &mut FirstUnmarked, &mut FirstUnmarked);
+
+        let output = String::from_utf8(out).unwrap();
+        let (games, summary) = output.split_once("\n\n").unwrap();
+
+        assert_eq!(games, "x".repeat(300));
+        assert_eq!(summary, "\
+Games: 300
+x won: 300 (100.0%, 95% CI 98.7%-100.0%)
+o won: 0 (0.0%, 95% CI 0.0%-1.3%)
+Drawn: 0 (0.0%, 95% CI 0.0%-1.3%)
+Average length: 7.0 moves
+First mover advantage: +100.0 points (first mover won 300, second mover won 0)
+");
+    }
+
+    #[test]
+    fn wilson_interval_is_narrower_with_more_games() {
+        let (low, high) = wilson_interval(5, 10);
+
+        assert!((low - 0.2366).abs() < 1e-4);
+        assert!((high - 0.7634).abs() < 1e-4);
+
+        let (low, high) = wilson_interval(500, 1000);
+
+        assert!(low > 0.46 && high < 0.54);
+
+        assert_eq!(wilson_interval(0, 20).0, 0.0);
+        assert_eq!(wilson_interval(20, 20).1, 1.0);
     }
-    std::io::stdout().flush().unwrap();
 }
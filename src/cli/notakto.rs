@@ -0,0 +1,145 @@
+use super::computer::{ History, Searchable };
+use super::interactive::{ format_position, format_winning_grid };
+use crate::ai::notakto;
+use crate::referee;
+use crate::{ BoardPlayError, Mark, MoveOutcome, NotaktoGame, NotaktoMove, Outcome };
+
+impl History for NotaktoGame {
+    type Move = NotaktoMove;
+
+    fn history(&self) -> &[(NotaktoMove, Mark)] {
+        self.history()
+    }
+
+    fn undo(&mut self) -> Option<(NotaktoMove, Mark)> {
+        self.undo()
+    }
+}
+
+impl Searchable for NotaktoGame {
+    const MEDIUM_DEPTH: usize = 2;
+
+    // The search goes all the way to the end of the game
+    const HARD_DEPTH: usize = usize::MAX;
+
+    const INTRO: &'static [&'static str] = &[
+        "Welcome to Notakto, where both players play x's",
+        "A board with three x's in a line is dead, and whoever kills the last board loses",
+        "Enter a move as \"board row col\""
+    ];
+
+    const MOVE_FORMAT: &'static [&'static str] = &[
+        "Try again, but this time enter a move in the format \"b r c\",",
+        "where b is the board and 1 <= r <= 3 and 1 <= c <= 3, for e.g. \"2 1 3\""
+    ];
+
+    type Error = BoardPlayError;
+
+    fn legal_moves(&self) -> Vec<NotaktoMove> {
+        self.legal_moves()
+    }
+
+    fn best_moves(&self, depth: usize) -> Vec<NotaktoMove> {
+        notakto::moves(self, depth)
+    }
+
+    fn play(&mut self, (board, cell): NotaktoMove) -> Result<MoveOutcome, BoardPlayError> {
+        self.play(board, cell)
+    }
+
+    fn restart(&mut self) {
+        self.restart()
+    }
+
+    fn turn(&self) -> Mark {
+        self.turn()
+    }
+
+    fn outcome(&self) -> Option<Outcome> {
+        self.outcome()
+    }
+
+    fn parse_move(s: &str) -> Option<NotaktoMove> {
+        let parts = s.split_ascii_whitespace().map(|part| part.parse::<usize>()).collect::<Vec<_>>();
+
+        match &parts[..] {
+            &[Ok(b), Ok(r), Ok(c)] if b > 0 && r > 0 && c > 0 => Some((b - 1, (r - 1, c - 1))),
+            _ => None
+        }
+    }
+
+    fn format_move((board, cell): NotaktoMove) -> String {
+        format!("at {} on board {}", format_position(cell), board + 1)
+    }
+
+    fn format_game(&self) -> String {
+        format_boards(self)
+    }
+}
+
+// OUTPUT
+
+/// Formats the boards side by side, with the lines that killed the dead boards in uppercase.
+fn format_boards(game: &NotaktoGame) -> String {
+    let columns = game
+        .boards()
+        .iter()
+        .enumerate()
+        .map(|(i, grid)| {
+            let header = if game.is_dead(i) {
+                format!("Board {} (dead)", i + 1)
+            } else {
+                format!("Board {}", i + 1)
+            };
+            let grid = format_winning_grid(grid, &referee::completed_lines(grid, Mark::X).concat());
+
+            std::iter::once(header).chain(grid.lines().map(String::from)).collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    (0..columns[0].len())
+        .map(|row| {
+            columns
+                .iter()
+                .map(|column| format!("{:<14}", column[row]))
+                .collect::<Vec<_>>()
+                .join("  ")
+                .trim_end()
+                .to_owned()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn moves_are_given_as_board_row_and_column() {
+        assert_eq!(NotaktoGame::parse_move("2 1 3"), Some((1, (0, 2))));
+        assert_eq!(NotaktoGame::parse_move("1 1"), None);
+        assert_eq!(NotaktoGame::parse_move("0 1 1"), None);
+        assert_eq!(NotaktoGame::format_move((1, (0, 2))), "at (1, 3) on board 2");
+    }
+
+    #[test]
+    fn the_boards_are_shown_side_by_side() {
+        let mut game = NotaktoGame::start_with_boards(Mark::X, 2);
+
+        for &cell in &[(0, 0), (1, 1), (2, 2)] {
+            game.play(0, cell).unwrap();
+        }
+
+        game.play(1, (0, 1)).unwrap();
+
+        assert_eq!(format_boards(&game).lines().collect::<Vec<_>>(), vec![
+            "Board 1 (dead)  Board 2",
+            " X |   |           | x |",
+            "---+---+---     ---+---+---",
+            "   | X |           |   |",
+            "---+---+---     ---+---+---",
+            "   |   | X         |   |"
+        ]);
+    }
+}
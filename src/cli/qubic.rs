@@ -0,0 +1,149 @@
+use super::computer::{ History, Searchable };
+use super::interactive::format_winning_grid;
+use crate::ai::qubic;
+use crate::{ Grid, Mark, MoveOutcome, Outcome, PlayError, QubicGame, QubicPosition, Shape };
+
+impl History for QubicGame {
+    type Move = QubicPosition;
+
+    fn history(&self) -> &[(QubicPosition, Mark)] {
+        self.history()
+    }
+
+    fn undo(&mut self) -> Option<(QubicPosition, Mark)> {
+        self.undo()
+    }
+}
+
+impl Searchable for QubicGame {
+    const MEDIUM_DEPTH: usize = 2;
+    const HARD_DEPTH: usize = 4;
+
+    const INTRO: &'static [&'static str] = &[
+        "Welcome to Qubic, Tic-tac-toe on a 4x4x4 cube",
+        "Get 4 in a row on a layer, down through the layers, or diagonally through the cube",
+        "Enter a position as \"layer row col\""
+    ];
+
+    const MOVE_FORMAT: &'static [&'static str] = &[
+        "Try again, but this time enter a position in the format \"layer row col\",",
+        "where each of them is between 1 and 4, for e.g. \"2 3 1\""
+    ];
+
+    type Error = PlayError;
+
+    fn legal_moves(&self) -> Vec<QubicPosition> {
+        self.legal_moves()
+    }
+
+    fn best_moves(&self, depth: usize) -> Vec<QubicPosition> {
+        qubic::moves(self, depth)
+    }
+
+    fn play(&mut self, p: QubicPosition) -> Result<MoveOutcome, PlayError> {
+        self.play(p)
+    }
+
+    fn restart(&mut self) {
+        self.restart()
+    }
+
+    fn turn(&self) -> Mark {
+        self.turn()
+    }
+
+    fn outcome(&self) -> Option<Outcome> {
+        self.outcome()
+    }
+
+    fn parse_move(s: &str) -> Option<QubicPosition> {
+        let parts = s.split_ascii_whitespace().map(|part| part.parse::<usize>()).collect::<Vec<_>>();
+
+        match &parts[..] {
+            &[Ok(l), Ok(r), Ok(c)] if l > 0 && r > 0 && c > 0 => Some((l - 1, r - 1, c - 1)),
+            _ => None
+        }
+    }
+
+    fn format_move((l, r, c): QubicPosition) -> String {
+        format!("at ({}, {}, {})", l + 1, r + 1, c + 1)
+    }
+
+    fn format_game(&self) -> String {
+        format_cube(self)
+    }
+}
+
+// OUTPUT
+
+/// Formats the cube as its 4 layers, one above the other, with the marks on the winning lines
+/// in uppercase.
+fn format_cube(game: &QubicGame) -> String {
+    let winning = game.winning_lines().concat();
+
+    (0..4)
+        .map(|l| {
+            let mut grid = Grid::with_shape(Shape::new(4, 4, 4));
+            let mut on_winning_lines = Vec::new();
+
+            for r in 0..4 {
+                for c in 0..4 {
+                    if let Some(mark) = game.cell_at((l, r, c)) {
+                        grid.mark((r, c), mark);
+                    }
+
+                    if winning.contains(&(l, r, c)) {
+                        on_winning_lines.push((r, c));
+                    }
+                }
+            }
+
+            format!("Layer {}\n{}", l + 1, format_winning_grid(&grid, &on_winning_lines))
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn positions_are_given_as_layer_row_and_column() {
+        assert_eq!(QubicGame::parse_move("2 3 1"), Some((1, 2, 0)));
+        assert_eq!(QubicGame::parse_move(" 4  4 4 "), Some((3, 3, 3)));
+        assert_eq!(QubicGame::parse_move("0 1 1"), None);
+        assert_eq!(QubicGame::parse_move("1 1"), None);
+        assert_eq!(QubicGame::parse_move("1 1 x"), None);
+        assert_eq!(QubicGame::format_move((1, 2, 0)), "at (2, 3, 1)");
+    }
+
+    #[test]
+    fn the_cube_is_shown_layer_by_layer() {
+        let mut game = QubicGame::start(Mark::X);
+
+        for l in 0..3 {
+            game.play((l, 0, 0)).unwrap();
+            game.play((l, 3, 3)).unwrap();
+        }
+
+        game.play((3, 0, 0)).unwrap();
+
+        let cube = format_cube(&game);
+        let layers = cube.split("\n\n").collect::<Vec<_>>();
+
+        assert_eq!(layers.len(), 4);
+        // X won down the pillar in the top left corner
+        assert_eq!(layers[3].lines().map(str::trim_end).collect::<Vec<_>>(), vec![
+            "Layer 4",
+            " X |   |   |",
+            "---+---+---+---",
+            "   |   |   |",
+            "---+---+---+---",
+            "   |   |   |",
+            "---+---+---+---",
+            "   |   |   |"
+        ]);
+        assert!(layers[0].ends_with(" |   |   | o"));
+    }
+}
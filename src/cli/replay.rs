@@ -0,0 +1,221 @@
+use std::io::{ BufRead, Write };
+
+use super::analysis::rank;
+use super::interactive::{ format_grid, format_position };
+use crate::ai::{ self, Evaluation };
+use crate::{ Outcome, Position, Record };
+
+/// How a move compares with the best move that could have been played instead.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum Annotation {
+    /// Nothing better could have been played.
+    Best,
+
+    /// It gets the same result as the best move but takes longer to win, or loses sooner.
+    Inaccuracy,
+
+    /// It gives up a better result.
+    Blunder
+}
+
+/// Asks for the next move to be shown.
+const PROMPT: &str = "Press Enter for the next move, or q to quit ";
+
+/// Steps through the game in the `record`, move by move, showing the grid after each move along
+/// with what the computer thinks of it. Each move is shown once Enter is pressed, until the game
+/// ends or "q" is entered.
+///
+/// The computer only thinks about the moves on grids that [`ai::can_solve`], since it searches
+/// every position to the end of the game.
+///
+/// [`ai::can_solve`]: ../../ai/fn.can_solve.html
+pub fn run(record: Record) {
+    replay(&mut std::io::stdin().lock(), &mut std::io::stdout(), record);
+}
+
+fn replay(input: &mut impl BufRead, out: &mut impl Write, record: Record) {
+    let mut game = record.into_game();
+    let moves = game.moves_played();
+    let mut number = moves;
+
+    while game.undo().is_some() {
+        number -= 1;
+    }
+
+    let is_evaluated = ai::can_solve(game.grid().shape());
+
+    if !is_evaluated {
+        writeln!(out, "The grid is too large for the computer to evaluate the moves").unwrap();
+    }
+
+    writeln!(out, "{}\n", format_grid(game.grid())).unwrap();
+
+    while number < moves {
+        if !read_next(input, out) {
+            return;
+        }
+
+        let evaluations = if is_evaluated { Some(ai::evaluate_moves(&game)) } else { None };
+        let (pos, mark) = game.redo().unwrap();
+
+        number += 1;
+
+        write!(out, "{}. {} plays at {}", number, mark, format_position(pos)).unwrap();
+
+        match evaluations {
+            Some(evaluations) => {
+                let (annotation, best) = annotate(pos, &evaluations);
+                let evaluation = evaluations.iter().find(|(p, _)| *p == pos).unwrap().1;
+
+                match annotation {
+                    Annotation::Best => writeln!(out, ", {} (best)", evaluation).unwrap(),
+                    Annotation::Inaccuracy => writeln!(out, ", {} (inaccuracy, {} {})", evaluation, format_position(best.0), best.1).unwrap(),
+                    Annotation::Blunder => writeln!(out, ", {} (blunder, {} {})", evaluation, format_position(best.0), best.1).unwrap()
+                }
+            },
+            None => writeln!(out).unwrap()
+        }
+
+        writeln!(out, "{}\n", format_grid(game.grid())).unwrap();
+    }
+
+    match game.outcome() {
+        Some(Outcome::Win(winner)) => writeln!(out, "{} won", winner).unwrap(),
+        Some(Outcome::Draw) => writeln!(out, "Game drawn").unwrap(),
+        None => writeln!(out, "The game wasn't finished").unwrap()
+    }
+}
+
+/// Waits for the next move to be asked for, and returns `false` if the replay should stop instead,
+/// either because "q" was entered or because there's no more input.
+fn read_next(input: &mut impl BufRead, out: &mut impl Write) -> bool {
+    write!(out, "{}", PROMPT).unwrap();
+    out.flush().unwrap();
+
+    let mut line = String::new();
+
+    match input.read_line(&mut line) {
+        Ok(0) | Err(_) => false,
+        Ok(_) => !line.trim().eq_ignore_ascii_case("q")
+    }
+}
+
+/// Annotates the move played at `pos`, given the [`Evaluation`] of every move that could have
+/// been played, and returns the best of those moves along with it.
+///
+/// [`Evaluation`]: ../../ai/struct.Evaluation.html
+fn annotate(pos: Position, evaluations: &[(Position, Evaluation)]) -> (Annotation, (Position, Evaluation)) {
+    let played = evaluations.iter().find(|(p, _)| *p == pos).unwrap().1;
+    let best = *evaluations.iter().max_by_key(|(_, e)| rank(*e)).unwrap();
+
+    let annotation = if rank(played) == rank(best.1) {
+        Annotation::Best
+    } else if played.value == best.1.value {
+        Annotation::Inaccuracy
+    } else {
+        Annotation::Blunder
+    };
+
+    (annotation, best)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::Value;
+    use crate::{ Game, Mark, Shape };
+
+    #[test]
+    fn moves_are_annotated_against_the_best_move() {
+        let evaluations = [
+            ((0, 0), Evaluation { value: Value::Win, plies: 3 }),
+            ((0, 1), Evaluation { value: Value::Win, plies: 1 }),
+            ((0, 2), Evaluation { value: Value::Draw, plies: 4 })
+        ];
+        let best = evaluations[1];
+
+        assert_eq!(annotate((0, 1), &evaluations), (Annotation::Best, best));
+        assert_eq!(annotate((0, 0), &evaluations), (Annotation::Inaccuracy, best));
+        assert_eq!(annotate((0, 2), &evaluations), (Annotation::Blunder, best));
+
+        // Holding out for longer is the best a lost position allows
+        let evaluations = [
+            ((1, 0), Evaluation { value: Value::Loss, plies: 2 }),
+            ((1, 1), Evaluation { value: Value::Loss, plies: 4 })
+        ];
+
+        assert_eq!(annotate((1, 1), &evaluations).0, Annotation::Best);
+        assert_eq!(annotate((1, 0), &evaluations).0, Annotation::Inaccuracy);
+    }
+
+    fn replay_with_input(input: &str) -> String {
+        let mut game = Game::start(Mark::X);
+
+        for &pos in &[(0, 0), (0, 1), (1, 1), (2, 2), (1, 0), (2, 0), (1, 2)] {
+            game.play(pos).unwrap();
+        }
+
+        replay_game(input, game)
+    }
+
+    fn replay_game(input: &str, game: Game) -> String {
+        let mut out = Vec::new();
+
+        replay(&mut input.as_bytes(), &mut out, Record::new(game));
+
+        String::from_utf8(out).unwrap()
+    }
+
+    fn moves_shown(out: &str) -> Vec<&str> {
+        out.lines().map(|line| line.trim_start_matches(PROMPT)).filter(|line| line.contains("plays at")).collect()
+    }
+
+    #[test]
+    fn a_replay_shows_every_move() {
+        let out = replay_with_input(&"\n".repeat(7));
+        let moves = moves_shown(&out);
+
+        assert_eq!(moves, vec![
+            "1. x plays at (1, 1), draws in 9 (best)",
+            "2. o plays at (1, 2), loses in 6 (blunder, (2, 2) draws in 8)",
+            "3. x plays at (2, 2), wins in 5 (best)",
+            "4. o plays at (3, 3), loses in 4 (best)",
+            "5. x plays at (2, 1), wins in 3 (best)",
+            "6. o plays at (3, 1), loses in 2 (best)",
+            "7. x plays at (2, 3), wins in 1 (best)"
+        ]);
+        assert!(out.ends_with("x won\n"));
+    }
+
+    #[test]
+    fn a_replay_steps_through_the_moves_until_it_is_quit() {
+        let out = replay_with_input("\n\nq\n");
+
+        assert_eq!(moves_shown(&out), vec![
+            "1. x plays at (1, 1), draws in 9 (best)",
+            "2. o plays at (1, 2), loses in 6 (blunder, (2, 2) draws in 8)"
+        ]);
+        assert_eq!(out.matches(PROMPT).count(), 3);
+        assert!(out.ends_with(PROMPT));
+
+        // Running out of input stops the replay too
+        let out = replay_with_input("\n");
+
+        assert_eq!(moves_shown(&out).len(), 1);
+        assert!(!out.contains("won"));
+    }
+
+    #[test]
+    fn the_moves_on_a_larger_grid_are_not_evaluated() {
+        let mut game = Game::start_with_shape(Mark::X, Shape::new(4, 4, 4));
+
+        game.play((0, 0)).unwrap();
+        game.play((3, 3)).unwrap();
+
+        let out = replay_game("\n\n", game);
+
+        assert!(out.contains("too large"));
+        assert_eq!(moves_shown(&out), vec!["1. x plays at (1, 1)", "2. o plays at (4, 4)"]);
+        assert!(out.ends_with("The game wasn't finished\n"));
+    }
+}
@@ -0,0 +1,179 @@
+use super::computer::{ History, Searchable };
+use super::interactive::{ format_position, parse_position };
+use crate::ai::ultimate;
+use crate::{ BoardPlayError, Mark, MoveOutcome, Outcome, Position, UltimateGame, UltimateMove };
+
+impl History for UltimateGame {
+    type Move = UltimateMove;
+
+    fn history(&self) -> &[(UltimateMove, Mark)] {
+        self.history()
+    }
+
+    fn undo(&mut self) -> Option<(UltimateMove, Mark)> {
+        self.undo()
+    }
+}
+
+/// Moves are given as positions on the 9x9 grid of cells, so the board is worked out from the
+/// cell.
+impl Searchable for UltimateGame {
+    const MEDIUM_DEPTH: usize = 2;
+    const HARD_DEPTH: usize = 5;
+
+    const INTRO: &'static [&'static str] = &[
+        "Welcome to Ultimate Tic-tac-toe",
+        "Win three boards in a line to win the game",
+        "The cell you mark sends your opponent to the board in the same place"
+    ];
+
+    const MOVE_FORMAT: &'static [&'static str] = &[
+        "Try again, but this time enter a position in the format \"r c\",",
+        "where 1 <= r <= 9 and 1 <= c <= 9, for e.g. \"5 5\""
+    ];
+
+    type Error = BoardPlayError;
+
+    fn legal_moves(&self) -> Vec<UltimateMove> {
+        self.legal_moves()
+    }
+
+    fn best_moves(&self, depth: usize) -> Vec<UltimateMove> {
+        ultimate::moves(self, depth)
+    }
+
+    fn play(&mut self, (board, cell): UltimateMove) -> Result<MoveOutcome, BoardPlayError> {
+        self.play(board, cell)
+    }
+
+    fn restart(&mut self) {
+        self.restart()
+    }
+
+    fn turn(&self) -> Mark {
+        self.turn()
+    }
+
+    fn outcome(&self) -> Option<Outcome> {
+        self.outcome()
+    }
+
+    fn parse_move(s: &str) -> Option<UltimateMove> {
+        match parse_position(s) {
+            Some(pos) if pos.0 < 9 && pos.1 < 9 => Some(from_cell(pos)),
+            _ => None
+        }
+    }
+
+    fn format_move(mv: UltimateMove) -> String {
+        format!("at {}", format_position(to_cell(mv)))
+    }
+
+    fn format_game(&self) -> String {
+        if self.is_playing() {
+            format!("{}\n{}", format_cells(self), format_boards(self))
+        } else {
+            format_cells(self)
+        }
+    }
+}
+
+/// The position of the cell that a move marks, on the 9x9 grid of cells.
+fn to_cell(((br, bc), (r, c)): UltimateMove) -> Position {
+    (br * 3 + r, bc * 3 + c)
+}
+
+/// The move that marks the cell at the given position on the 9x9 grid of cells.
+fn from_cell((r, c): Position) -> UltimateMove {
+    ((r / 3, c / 3), (r % 3, c % 3))
+}
+
+// OUTPUT
+
+/// Formats the boards that the next move can be played on.
+fn format_boards(game: &UltimateGame) -> String {
+    match &game.boards()[..] {
+        [board] => format!("Play on the board at {}", format_position(*board)),
+        _ => String::from("Play on any open board")
+    }
+}
+
+/// Formats the 9x9 grid of cells, with the boards that have been won filled in with the
+/// winner's mark in uppercase and the cells that can be played on shown as dots.
+fn format_cells(game: &UltimateGame) -> String {
+    let boards = game.boards();
+    let header = format!("   {}", ["1 2 3", "4 5 6", "7 8 9"].join("   "));
+    let sep = format!("   {}", ["-----"; 3].join("-+-"));
+
+    let rows = (0..9)
+        .map(|r| {
+            let cells = (0..3)
+                .map(|bc| {
+                    (0..3)
+                        .map(|c| {
+                            let (board, cell) = from_cell((r, bc * 3 + c));
+
+                            format_cell(game, board, cell, boards.contains(&board))
+                        })
+                        .collect::<Vec<_>>()
+                        .join(" ")
+                })
+                .collect::<Vec<_>>();
+
+            format!("{}  {}", r + 1, cells.join(" | ")).trim_end().to_owned()
+        })
+        .collect::<Vec<_>>();
+    let bands = rows.chunks(3).map(|band| band.join("\n")).collect::<Vec<_>>();
+
+    format!("{}\n{}", header, bands.join(&format!("\n{}\n", sep)))
+}
+
+fn format_cell(game: &UltimateGame, board: Position, cell: Position, is_playable: bool) -> String {
+    match (game.board_outcome(board), game.board(board).cell_at(cell)) {
+        (Some(Outcome::Win(winner)), _) => winner.to_string().to_uppercase(),
+        (_, Some(mark)) => mark.to_string(),
+        (_, None) if is_playable => String::from("."),
+        (_, None) => String::from(" ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cells_and_moves() {
+        assert_eq!(from_cell((4, 7)), ((1, 2), (1, 1)));
+        assert_eq!(to_cell(((1, 2), (1, 1))), (4, 7));
+
+        for r in 0..9 {
+            for c in 0..9 {
+                assert_eq!(to_cell(from_cell((r, c))), (r, c));
+            }
+        }
+    }
+
+    #[test]
+    fn the_game_is_shown_as_a_9x9_grid() {
+        let mut game = UltimateGame::start(Mark::X);
+
+        game.play((1, 1), (0, 0)).unwrap();
+        game.play((0, 0), (1, 1)).unwrap();
+
+        // X is sent to the middle board, so its cells are shown as dots
+        assert_eq!(format_cells(&game).lines().collect::<Vec<_>>(), vec![
+            "   1 2 3   4 5 6   7 8 9",
+            "1        |       |",
+            "2    o   |       |",
+            "3        |       |",
+            "   ------+-------+------",
+            "4        | x . . |",
+            "5        | . . . |",
+            "6        | . . . |",
+            "   ------+-------+------",
+            "7        |       |",
+            "8        |       |",
+            "9        |       |"
+        ]);
+    }
+}
@@ -0,0 +1,95 @@
+use super::computer::{ History, Searchable };
+use super::interactive::{ format_position, format_winning_grid };
+use crate::ai::wild;
+use crate::{ Mark, MoveOutcome, Outcome, PlayError, WildGame, WildMove };
+
+impl History for WildGame {
+    type Move = WildMove;
+
+    fn history(&self) -> &[(WildMove, Mark)] {
+        self.history()
+    }
+
+    fn undo(&mut self) -> Option<(WildMove, Mark)> {
+        self.undo()
+    }
+}
+
+impl Searchable for WildGame {
+    const MEDIUM_DEPTH: usize = 2;
+
+    // Enough to search the 3x3 grid to the end
+    const HARD_DEPTH: usize = 9;
+
+    const INTRO: &'static [&'static str] = &[
+        "Welcome to Wild Tic-tac-toe",
+        "On your turn put down either an x or an o, and complete a line of either to win",
+        "Enter a move as \"row col mark\", for e.g. \"2 3 o\""
+    ];
+
+    const MOVE_FORMAT: &'static [&'static str] = &[
+        "Try again, but this time enter a move in the format \"r c m\",",
+        "where 1 <= r <= 3, 1 <= c <= 3 and m is x or o, for e.g. \"2 3 o\""
+    ];
+
+    type Error = PlayError;
+
+    fn legal_moves(&self) -> Vec<WildMove> {
+        self.legal_moves()
+    }
+
+    fn best_moves(&self, depth: usize) -> Vec<WildMove> {
+        wild::moves(self, depth)
+    }
+
+    fn play(&mut self, (p, m): WildMove) -> Result<MoveOutcome, PlayError> {
+        self.play(p, m)
+    }
+
+    fn restart(&mut self) {
+        self.restart()
+    }
+
+    fn turn(&self) -> Mark {
+        self.turn()
+    }
+
+    fn outcome(&self) -> Option<Outcome> {
+        self.outcome()
+    }
+
+    fn parse_move(s: &str) -> Option<WildMove> {
+        let parts = s.split_ascii_whitespace().collect::<Vec<_>>();
+
+        match &parts[..] {
+            &[r, c, m] => match (r.parse::<usize>(), c.parse::<usize>(), super::parse_mark(m)) {
+                (Ok(r), Ok(c), Ok(m)) if r > 0 && c > 0 => Some(((r - 1, c - 1), m)),
+                _ => None
+            },
+            _ => None
+        }
+    }
+
+    fn format_move((p, m): WildMove) -> String {
+        format!("{} at {}", m, format_position(p))
+    }
+
+    fn format_game(&self) -> String {
+        format_winning_grid(self.grid(), &self.winning_lines().concat())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn moves_are_given_as_row_column_and_mark() {
+        assert_eq!(WildGame::parse_move("2 3 o"), Some(((1, 2), Mark::O)));
+        assert_eq!(WildGame::parse_move(" 1  1 X "), Some(((0, 0), Mark::X)));
+        assert_eq!(WildGame::parse_move("2 3"), None);
+        assert_eq!(WildGame::parse_move("0 1 x"), None);
+        assert_eq!(WildGame::parse_move("1 1 z"), None);
+        assert_eq!(WildGame::format_move(((1, 2), Mark::O)), "o at (2, 3)");
+    }
+}
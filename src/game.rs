@@ -1,6 +1,10 @@
-use crate::grid::{ Grid, Position };
+use std::error::Error;
+use std::fmt;
+
+use crate::ai;
+use crate::grid::{ Grid, Position, Shape };
 use crate::mark::Mark;
-use crate::referee::{ self, Outcome };
+use crate::referee::{ self, DrawRule, Outcome, Rules, Variant };
 
 /// The game logic for Tic-tac-toe.
 ///
@@ -20,40 +24,41 @@ use crate::referee::{ self, Outcome };
 /// # Examples
 ///
 /// ```
-/// use xsos::{ Game, Mark, Outcome, PlayError };
+/// use xsos::{ Game, Mark, MoveOutcome, Outcome, PlayError };
 ///
+/// # fn main() -> Result<(), PlayError> {
 /// // Start a new game and let X play first
 /// let mut game = Game::start(Mark::X);
 ///
 /// // Mark X at (0, 2)
-/// game.play((0, 2));
+/// game.play((0, 2))?;
 ///
 /// // Mark O at (1, 2)
-/// game.play((1, 2));
+/// game.play((1, 2))?;
 ///
 /// // Notice that you don't pass the mark since that's managed internally
 ///
 /// assert_eq!(game.turn(), Mark::X);
 ///
 /// // Mark X at (1, 1)
-/// game.play((1, 1));
+/// game.play((1, 1))?;
 ///
 /// assert_eq!(game.turn(), Mark::O);
 ///
 /// // Try to mark O on a marked cell
-/// assert_eq!(game.play((1, 1)), Some(PlayError::AlreadyMarked));
+/// assert_eq!(game.play((1, 1)), Err(PlayError::AlreadyMarked));
 ///
 /// // Still O's turn
 /// // Try to mark O at some position outside the grid
-/// assert_eq!(game.play((3, 3)), Some(PlayError::OutOfBounds));
+/// assert_eq!(game.play((3, 3)), Err(PlayError::OutOfBounds));
 ///
 /// // Still O's turn, so mark O at (2, 2)
-/// game.play((2, 2));
+/// assert_eq!(game.play((2, 2))?, MoveOutcome::InPlay);
 ///
 /// assert!(game.is_playing());
 ///
 /// // Take the win by marking X at (2, 0)
-/// game.play((2, 0));
+/// assert_eq!(game.play((2, 0))?, MoveOutcome::GameOver(Outcome::Win(Mark::X)));
 ///
 /// //   0   1   2
 /// // 0   |   | x
@@ -62,10 +67,11 @@ use crate::referee::{ self, Outcome };
 /// //  ---+---+---
 /// // 2 x |   | o
 ///
-/// // The game is over and X won
+/// // The game is over and X won along the diagonal
 /// assert!(game.is_game_over());
 /// assert_eq!(game.turn(), Mark::X);
-/// assert_eq!(game.outcome(), Some(Outcome::Win));
+/// assert_eq!(game.outcome(), Some(Outcome::Win(Mark::X)));
+/// assert_eq!(game.winning_lines(), vec![vec![(0, 2), (1, 1), (2, 0)]]);
 ///
 /// // Want to play another? Just restart the game. The player that won
 /// // gets to play first in the restarted game. However, if the game
@@ -78,15 +84,15 @@ use crate::referee::{ self, Outcome };
 /// assert_eq!(game.outcome(), None);
 ///
 /// // Let's draw this one
-/// game.play((1, 1));
-/// game.play((0, 0));
-/// game.play((0, 1));
-/// game.play((2, 1));
-/// game.play((2, 0));
-/// game.play((0, 2));
-/// game.play((1, 2));
-/// game.play((1, 0));
-/// game.play((2, 2));
+/// game.play((1, 1))?;
+/// game.play((0, 0))?;
+/// game.play((0, 1))?;
+/// game.play((2, 1))?;
+/// game.play((2, 0))?;
+/// game.play((0, 2))?;
+/// game.play((1, 2))?;
+/// game.play((1, 0))?;
+/// game.play((2, 2))?;
 ///
 /// assert!(game.is_game_over());
 /// assert_eq!(game.outcome(), Some(Outcome::Draw));
@@ -95,12 +101,17 @@ use crate::referee::{ self, Outcome };
 ///
 /// // See how O gets to play first this time around
 /// assert_eq!(game.turn(), Mark::O);
+/// # Ok(())
+/// # }
 /// ```
 #[derive(Clone)]
 pub struct Game {
     grid: Grid,
     turn: Mark,
-    state: State
+    state: State,
+    history: Vec<(Position, Mark)>,
+    undone: Vec<(Position, Mark)>,
+    rules: Rules
 }
 
 #[derive(Clone, Copy)]
@@ -109,18 +120,82 @@ enum State {
     GameOver(Outcome)
 }
 
+/// The result of a successful move in a [`Game`].
+///
+/// [`Game`]: ./struct.Game.html
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum MoveOutcome {
+    /// The game is still in play.
+    InPlay,
+
+    /// The move ended the game.
+    GameOver(Outcome)
+}
+
 /// The possible errors that can occur when playing a [`Game`].
 ///
 /// [`Game`]: ./struct.Game.html
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(rename_all = "snake_case"))]
 pub enum PlayError {
     /// Tried to mark a marked cell.
     AlreadyMarked,
 
-    /// Tried to play at a position, `p`, such that `Grid::in_bounds(p) == false`.
-    OutOfBounds
+    /// Tried to play at a position, `p`, such that `grid.shape().in_bounds(p) == false`.
+    OutOfBounds,
+
+    /// Tried to play after the game was over.
+    GameOver
+}
+
+impl fmt::Display for PlayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::AlreadyMarked => write!(f, "that position is already taken"),
+            Self::OutOfBounds => write!(f, "that position is out of bounds"),
+            Self::GameOver => write!(f, "the game is over")
+        }
+    }
+}
+
+impl Error for PlayError {}
+
+/// The possible errors that can occur when playing a game on several boards, i.e. an
+/// [`UltimateGame`] or a [`NotaktoGame`].
+///
+/// [`UltimateGame`]: ./struct.UltimateGame.html
+/// [`NotaktoGame`]: ./struct.NotaktoGame.html
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum BoardPlayError {
+    /// Tried to play a move that isn't allowed on any board, for the same reasons as in a
+    /// [`Game`].
+    ///
+    /// [`Game`]: ./struct.Game.html
+    Play(PlayError),
+
+    /// Tried to play on a board other than the one the last move sent the player to, or on a
+    /// board that's already closed, in an `UltimateGame`. Or tried to play on a dead board in a
+    /// `NotaktoGame`.
+    WrongBoard
+}
+
+impl From<PlayError> for BoardPlayError {
+    fn from(error: PlayError) -> Self {
+        Self::Play(error)
+    }
+}
+
+impl fmt::Display for BoardPlayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Play(error) => error.fmt(f),
+            Self::WrongBoard => write!(f, "you can't play on that board")
+        }
+    }
 }
 
+impl Error for BoardPlayError {}
+
 impl Game {
     /// Start a new game and let `first` play first.
     ///
@@ -134,22 +209,138 @@ impl Game {
     /// assert_eq!(game.turn(), Mark::X);
     /// ```
     pub fn start(first: Mark) -> Self {
+        Self::start_with_shape(first, Shape::default())
+    }
+
+    /// Start a new game on a [`Grid`] with the given [`Shape`] and let `first` play first.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use xsos::{ Game, Mark, Outcome, Shape };
+    ///
+    /// // Get 4 in a row on a 4x4 grid
+    /// let mut game = Game::start_with_shape(Mark::X, Shape::new(4, 4, 4));
+    ///
+    /// for c in 0..3 {
+    ///     game.play((0, c)).unwrap();
+    ///     game.play((1, c)).unwrap();
+    /// }
+    ///
+    /// assert!(game.is_playing());
+    ///
+    /// game.play((0, 3)).unwrap();
+    ///
+    /// assert_eq!(game.outcome(), Some(Outcome::Win(Mark::X)));
+    /// ```
+    ///
+    /// [`Grid`]: ./struct.Grid.html
+    /// [`Shape`]: ./struct.Shape.html
+    pub fn start_with_shape(first: Mark, shape: Shape) -> Self {
         Self {
-            grid: Grid::new(),
+            grid: Grid::with_shape(shape),
             turn: first,
-            state: State::Play
+            state: State::Play,
+            history: Vec::new(),
+            undone: Vec::new(),
+            rules: Rules::default()
         }
     }
 
-    /// Restart a game.
-    pub fn restart(&mut self) {
-        self.grid = Grid::new();
+    /// Creates a `Game` in the position given by the `grid`, where it's the given `turn`.
+    ///
+    /// The position is assumed to be reachable, with `grid.last_mark()` being the mark of the
+    /// player who moved last.
+    pub(crate) fn from_position(grid: Grid, turn: Mark) -> Self {
+        let rules = Rules::default();
+        let state = match referee::evaluate(&grid, rules) {
+            Some(outcome) => State::GameOver(outcome),
+            None => State::Play
+        };
+
+        Self { grid, turn, state, history: Vec::new(), undone: Vec::new(), rules }
+    }
+
+    /// Sets the [`DrawRule`] that decides when this `Game` is drawn. By default it's `DrawRule::FullGrid`.
+    ///
+    /// It's meant to be called when the game is started and it applies to every restarted game too.
+    /// A game that's started from a position that's drawn under the rule is over straight away.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use xsos::{ DrawRule, Game, Mark };
+    ///
+    /// let game = Game::start(Mark::X).with_draw_rule(DrawRule::Blocked);
+    ///
+    /// assert_eq!(game.draw_rule(), DrawRule::Blocked);
+    /// ```
+    ///
+    /// [`DrawRule`]: ./enum.DrawRule.html
+    pub fn with_draw_rule(mut self, draw: DrawRule) -> Self {
+        self.rules.draw = draw;
+        self.decide_start();
+        self
+    }
+
+    /// Returns the [`DrawRule`] that decides when this `Game` is drawn.
+    ///
+    /// [`DrawRule`]: ./enum.DrawRule.html
+    pub fn draw_rule(&self) -> DrawRule {
+        self.rules.draw
+    }
+
+    /// Sets the [`Variant`] that decides whether completing a line wins or loses. By default
+    /// it's `Variant::Standard`.
+    ///
+    /// It's meant to be called when the game is started and it applies to every restarted game too.
+    /// A game that's started from a position is decided again under the variant, so it doesn't
+    /// matter whether it's set before or after the [`DrawRule`].
+    ///
+    /// [`DrawRule`]: ./enum.DrawRule.html
+    /// [`Variant`]: ./enum.Variant.html
+    pub fn with_variant(mut self, variant: Variant) -> Self {
+        self.rules.variant = variant;
+        self.decide_start();
+        self
+    }
 
-        if let State::GameOver(Outcome::Draw) = self.state {
-            self.turn = self.turn.swap();
+    /// Returns the [`Variant`] that decides whether completing a line wins or loses.
+    ///
+    /// [`Variant`]: ./enum.Variant.html
+    pub fn variant(&self) -> Variant {
+        self.rules.variant
+    }
+
+    /// Decides again whether a game that hasn't been played yet is over under its rules, since a
+    /// game started from a position may be won by the other player or drawn once they're set.
+    ///
+    /// As in `unchecked_play`, the perfect play search only decides positions that have been
+    /// played into, so an empty grid is left to be played.
+    fn decide_start(&mut self) {
+        if !self.history.is_empty() {
+            return;
         }
 
+        // The search has to see the position as still in play, whatever it was decided before
+        self.state = State::Play;
+        self.state = match referee::evaluate(&self.grid, self.rules) {
+            Some(outcome) => State::GameOver(outcome),
+            None if uses_perfect_play(self)
+                && self.grid.unmarked_positions().count() < self.grid.shape().ncells()
+                && ai::is_drawn_with_perfect_play(self) => State::GameOver(Outcome::Draw),
+            None => State::Play
+        };
+    }
+
+    /// Restart a game. The winner plays first in the restarted game, and after a draw the other
+    /// player gets to play first.
+    pub fn restart(&mut self) {
+        self.grid = Grid::with_shape(self.grid.shape());
+        self.turn = first_turn(self.turn, self.outcome());
         self.state = State::Play;
+        self.history.clear();
+        self.undone.clear();
     }
 
     /// Marks the [`Cell`] at the given [`Position`] on the [`Grid`] managed by this `Game`, say `game`,
@@ -157,31 +348,33 @@ impl Game {
     ///
     /// It returns:
     ///
-    /// - `Some(PlayError::OutOfBounds)`, if this `Game` is not over and `p` is out of bounds.
-    /// - `Some(PlayError::AlreadyMarked)`, if this `Game` is not over and the [`Cell`] at `p` is already marked.
-    /// - `None`, in all other cases.
+    /// - `Err(PlayError::GameOver)`, if this `Game` is over.
+    /// - `Err(PlayError::OutOfBounds)`, if `p` is out of bounds.
+    /// - `Err(PlayError::AlreadyMarked)`, if the [`Cell`] at `p` is already marked.
+    /// - `Ok(MoveOutcome::GameOver(outcome))`, if the move ended this `Game`.
+    /// - `Ok(MoveOutcome::InPlay)`, otherwise.
     ///
     /// # Examples
     ///
     /// ```
-    /// use xsos::{ Game, Mark, PlayError };
+    /// use xsos::{ Game, Mark, MoveOutcome, Outcome, PlayError };
     ///
     /// let mut game = Game::start(Mark::X);
     ///
-    /// assert_eq!(game.play((3, 3)), Some(PlayError::OutOfBounds));
-    /// assert_eq!(game.play((0, 0)), None);
-    /// assert_eq!(game.play((0, 0)), Some(PlayError::AlreadyMarked));
+    /// assert_eq!(game.play((3, 3)), Err(PlayError::OutOfBounds));
+    /// assert_eq!(game.play((0, 0)), Ok(MoveOutcome::InPlay));
+    /// assert_eq!(game.play((0, 0)), Err(PlayError::AlreadyMarked));
     ///
-    /// game.play((0, 1));
-    /// game.play((1, 0));
-    /// game.play((1, 1));
-    /// game.play((2, 0));
+    /// game.play((0, 1)).unwrap();
+    /// game.play((1, 0)).unwrap();
+    /// game.play((1, 1)).unwrap();
     ///
+    /// assert_eq!(game.play((2, 0)), Ok(MoveOutcome::GameOver(Outcome::Win(Mark::X))));
     /// assert!(game.is_game_over());
     ///
     /// // You can't make any plays once the game is over
     ///
-    /// assert_eq!(game.play((2, 1)), None);
+    /// assert_eq!(game.play((2, 1)), Err(PlayError::GameOver));
     /// assert!(game.grid().is_unmarked_at((2, 1)));
     /// ```
     ///
@@ -189,23 +382,98 @@ impl Game {
     /// [`Grid`]: ./struct.Grid.html
     /// [`Mark`]: ./enum.Mark.html
     /// [`Position`]: ./type.Position.html
-    pub fn play(&mut self, p: Position) -> Option<PlayError> {
-        if self.is_playing() {
-            if Grid::in_bounds(p) {
-                if self.grid.is_unmarked_at(p) {
-                    unchecked_play(self, p);
-                    None
-                } else {
-                    Some(PlayError::AlreadyMarked)
-                }
-            } else {
-                Some(PlayError::OutOfBounds)
-            }
+    pub fn play(&mut self, p: Position) -> Result<MoveOutcome, PlayError> {
+        if self.is_game_over() {
+            Err(PlayError::GameOver)
+        } else if !self.grid.shape().in_bounds(p) {
+            Err(PlayError::OutOfBounds)
+        } else if self.grid.is_marked_at(p) {
+            Err(PlayError::AlreadyMarked)
         } else {
-            None
+            unchecked_play(self, p);
+            self.undone.clear();
+
+            match self.outcome() {
+                Some(outcome) => Ok(MoveOutcome::GameOver(outcome)),
+                None => Ok(MoveOutcome::InPlay)
+            }
         }
     }
 
+    /// Takes back the last move, if any, and returns it.
+    ///
+    /// The [`Grid`], the turn and the state of this `Game` are restored to what they were
+    /// before the move was played. A move that's taken back can be played again with [`redo`]
+    /// up until a new move is played.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use xsos::{ Game, Mark };
+    ///
+    /// let mut game = Game::start(Mark::X);
+    ///
+    /// game.play((0, 0)).unwrap();
+    /// game.play((1, 0)).unwrap();
+    /// game.play((0, 1)).unwrap();
+    /// game.play((1, 1)).unwrap();
+    /// game.play((0, 2)).unwrap();
+    ///
+    /// assert!(game.is_game_over());
+    ///
+    /// assert_eq!(game.undo(), Some(((0, 2), Mark::X)));
+    /// assert!(game.is_playing());
+    /// assert_eq!(game.turn(), Mark::X);
+    /// assert_eq!(game.moves_played(), 4);
+    ///
+    /// assert_eq!(game.undo(), Some(((1, 1), Mark::O)));
+    /// assert_eq!(game.turn(), Mark::O);
+    /// assert_eq!(game.grid().last_mark(), Some(Mark::X));
+    ///
+    /// assert_eq!(game.redo(), Some(((1, 1), Mark::O)));
+    /// assert_eq!(game.history(), &[((0, 0), Mark::X), ((1, 0), Mark::O), ((0, 1), Mark::X), ((1, 1), Mark::O)]);
+    /// ```
+    ///
+    /// [`Grid`]: ./struct.Grid.html
+    /// [`redo`]: #method.redo
+    pub fn undo(&mut self) -> Option<(Position, Mark)> {
+        let (p, m) = self.history.pop()?;
+
+        self.grid.unmark(p, None);
+
+        // Play alternates, so the mark before this one was the other player's, unless this was
+        // the first mark on the grid
+        let last = if self.grid.cells().any(Option::is_some) { Some(m.swap()) } else { None };
+
+        self.grid.set_last_mark(last);
+        self.turn = m;
+        self.state = State::Play;
+        self.undone.push((p, m));
+
+        Some((p, m))
+    }
+
+    /// Plays the last move that was taken back by [`undo`], if any, and returns it.
+    ///
+    /// [`undo`]: #method.undo
+    pub fn redo(&mut self) -> Option<(Position, Mark)> {
+        let (p, m) = self.undone.pop()?;
+
+        unchecked_play(self, p);
+
+        Some((p, m))
+    }
+
+    /// Returns the moves played so far, in the order they were played.
+    pub fn history(&self) -> &[(Position, Mark)] {
+        &self.history
+    }
+
+    /// Returns the number of moves played so far.
+    pub fn moves_played(&self) -> usize {
+        self.history.len()
+    }
+
     /// Returns `true` if this `Game` is in play.
     pub fn is_playing(&self) -> bool {
         matches!(self.state, State::Play)
@@ -223,6 +491,10 @@ impl Game {
         &self.grid
     }
 
+    pub(crate) fn rules(&self) -> Rules {
+        self.rules
+    }
+
     /// Returns the answer to the question "Who's turn is it to play?".
     pub fn turn(&self) -> Mark {
         self.turn
@@ -236,15 +508,85 @@ impl Game {
             None
         }
     }
+
+    /// Returns the positions of the lines that won this `Game`, if it was won. Otherwise it returns
+    /// an empty `Vec`.
+    ///
+    /// There can be more than one line since a single move can complete several lines at once.
+    /// In `Variant::Misere` these are the lines that the loser completed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use xsos::{ Game, Mark, Outcome };
+    ///
+    /// let mut game = Game::start(Mark::O);
+    ///
+    /// for &pos in &[(0, 1), (1, 1), (0, 2), (2, 2), (1, 0), (1, 2), (2, 0), (2, 1)] {
+    ///     game.play(pos).unwrap();
+    /// }
+    ///
+    /// assert!(game.winning_lines().is_empty());
+    ///
+    /// // Completes the top row and the left column
+    /// game.play((0, 0)).unwrap();
+    ///
+    /// assert_eq!(game.outcome(), Some(Outcome::Win(Mark::O)));
+    /// assert_eq!(game.winning_lines(), vec![
+    ///     vec![(0, 0), (0, 1), (0, 2)],
+    ///     vec![(0, 0), (1, 0), (2, 0)]
+    /// ]);
+    /// ```
+    pub fn winning_lines(&self) -> Vec<Vec<Position>> {
+        match (self.outcome(), self.rules.variant) {
+            (Some(Outcome::Win(winner)), Variant::Standard) => referee::completed_lines(&self.grid, winner),
+            (Some(Outcome::Win(winner)), Variant::Misere) => referee::completed_lines(&self.grid, winner.swap()),
+            _ => Vec::new()
+        }
+    }
 }
 
 pub fn unchecked_play(game: &mut Game, p: Position) {
     game.grid.mark(p, game.turn);
+    game.history.push((p, game.turn));
 
-    if let Some(outcome) = referee::evaluate(&game.grid) {
+    if let Some(outcome) = referee::evaluate(&game.grid, game.rules) {
         game.state = State::GameOver(outcome);
     } else {
         game.turn = game.turn.swap();
+
+        if uses_perfect_play(game) && ai::is_drawn_with_perfect_play(game) {
+            game.turn = game.turn.swap();
+            game.state = State::GameOver(Outcome::Draw);
+        }
+    }
+}
+
+/// Returns whether draws in the `game` are decided by the perfect play search. It's left out on
+/// grids too large for it to finish, where `DrawRule::PerfectPlay` works like `DrawRule::Blocked`.
+fn uses_perfect_play(game: &Game) -> bool {
+    game.rules.draw == DrawRule::PerfectPlay && ai::can_solve(game.grid.shape())
+}
+
+/// Returns whose turn it is after `mark` has moved, given the `outcome` of the move, for any of
+/// the games. Once it's game over the turn stays with whoever made the last move, so that
+/// [`first_turn`] can tell who that was.
+///
+/// [`first_turn`]: ./fn.first_turn.html
+pub(crate) fn next_turn(mark: Mark, outcome: Option<Outcome>) -> Mark {
+    match outcome {
+        Some(_) => mark,
+        None => mark.swap()
+    }
+}
+
+/// Returns who plays first when any of the games is restarted, given its `turn` and `outcome`:
+/// the winner, or after a draw the player who didn't make the last move.
+pub(crate) fn first_turn(turn: Mark, outcome: Option<Outcome>) -> Mark {
+    match outcome {
+        Some(Outcome::Win(winner)) => winner,
+        Some(Outcome::Draw) => turn.swap(),
+        None => turn
     }
 }
 
@@ -258,9 +600,9 @@ mod tests {
 
         assert!(game.is_playing());
 
-        game.play((1, 1));
-        game.play((0, 2));
-        game.play((2, 0));
+        game.play((1, 1)).unwrap();
+        game.play((0, 2)).unwrap();
+        game.play((2, 0)).unwrap();
 
         assert!(game.is_playing());
         assert_eq!(game.turn, Mark::O);
@@ -275,13 +617,13 @@ mod tests {
     fn when_x_wins() {
         let mut game = Game::start(Mark::X);
 
-        game.play((1, 1));
-        game.play((0, 2));
-        game.play((2, 0));
-        game.play((1, 2));
-        game.play((2, 2));
-        game.play((2, 1));
-        game.play((0, 0));
+        game.play((1, 1)).unwrap();
+        game.play((0, 2)).unwrap();
+        game.play((2, 0)).unwrap();
+        game.play((1, 2)).unwrap();
+        game.play((2, 2)).unwrap();
+        game.play((2, 1)).unwrap();
+        game.play((0, 0)).unwrap();
 
         assert!(game.is_game_over());
         assert_eq!(game.turn, Mark::X);
@@ -290,22 +632,22 @@ mod tests {
             &None, &Some(Mark::X), &Some(Mark::O),
             &Some(Mark::X), &Some(Mark::O), &Some(Mark::X)
         ]);
-        assert_eq!(game.outcome(), Some(Outcome::Win));
+        assert_eq!(game.outcome(), Some(Outcome::Win(Mark::X)));
     }
 
     #[test]
     fn when_o_squashes() {
         let mut game = Game::start(Mark::O);
 
-        game.play((1, 1));
-        game.play((0, 0));
-        game.play((2, 2));
-        game.play((0, 2));
-        game.play((0, 1));
-        game.play((2, 1));
-        game.play((1, 2));
-        game.play((1, 0));
-        game.play((2, 0));
+        game.play((1, 1)).unwrap();
+        game.play((0, 0)).unwrap();
+        game.play((2, 2)).unwrap();
+        game.play((0, 2)).unwrap();
+        game.play((0, 1)).unwrap();
+        game.play((2, 1)).unwrap();
+        game.play((1, 2)).unwrap();
+        game.play((1, 0)).unwrap();
+        game.play((2, 0)).unwrap();
 
         assert!(game.is_game_over());
         assert_eq!(game.turn, Mark::O);
@@ -321,34 +663,273 @@ mod tests {
     fn when_position_is_out_of_bounds() {
         let mut game = Game::start(Mark::X);
 
-        assert_eq!(game.play((0, 4)), Some(PlayError::OutOfBounds));
+        assert_eq!(game.play((0, 4)), Err(PlayError::OutOfBounds));
     }
 
     #[test]
     fn when_position_is_unavailable() {
         let mut game = Game::start(Mark::X);
 
-        game.play((1, 1));
+        game.play((1, 1)).unwrap();
+
+        assert_eq!(game.play((1, 1)), Err(PlayError::AlreadyMarked));
+    }
+
+    #[test]
+    fn when_the_game_is_over() {
+        let mut game = Game::start(Mark::X);
+
+        game.play((0, 0)).unwrap();
+        game.play((1, 0)).unwrap();
+        game.play((0, 1)).unwrap();
+        game.play((1, 1)).unwrap();
 
-        assert_eq!(game.play((1, 1)), Some(PlayError::AlreadyMarked));
+        assert_eq!(game.play((0, 2)), Ok(MoveOutcome::GameOver(Outcome::Win(Mark::X))));
+        assert_eq!(game.play((2, 2)), Err(PlayError::GameOver));
+        assert_eq!(game.play((5, 5)), Err(PlayError::GameOver));
+        assert_eq!(game.moves_played(), 5);
+        assert_eq!(PlayError::GameOver.to_string(), "the game is over");
+    }
+
+    #[test]
+    fn when_x_completes_a_line_in_misere() {
+        let mut game = Game::start(Mark::X).with_variant(Variant::Misere);
+
+        for &pos in &[(0, 0), (1, 0), (0, 1), (1, 1)] {
+            game.play(pos).unwrap();
+        }
+
+        assert_eq!(game.play((0, 2)), Ok(MoveOutcome::GameOver(Outcome::Win(Mark::O))));
+        assert_eq!(game.turn(), Mark::X);
+        assert_eq!(game.winning_lines(), vec![vec![(0, 0), (0, 1), (0, 2)]]);
+
+        // The winner plays first in the restarted game
+        game.restart();
+
+        assert_eq!(game.turn(), Mark::O);
+        assert_eq!(game.variant(), Variant::Misere);
+
+        // The variant decides who won a finished position too
+        let game = "xxx/oo./... x".parse::<Game>().unwrap().with_variant(Variant::Misere);
+
+        assert_eq!(game.outcome(), Some(Outcome::Win(Mark::O)));
     }
 
     #[test]
     fn clone() {
         let mut game = Game::start(Mark::X);
 
-        game.play((1, 1));
-        game.play((0, 2));
-        game.play((2, 0));
-        game.play((1, 2));
-        game.play((2, 2));
-        game.play((2, 1));
+        game.play((1, 1)).unwrap();
+        game.play((0, 2)).unwrap();
+        game.play((2, 0)).unwrap();
+        game.play((1, 2)).unwrap();
+        game.play((2, 2)).unwrap();
+        game.play((2, 1)).unwrap();
 
         let mut clone_of_game = game.clone();
 
-        clone_of_game.play((0, 0));
+        clone_of_game.play((0, 0)).unwrap();
 
         assert!(clone_of_game.is_game_over());
         assert!(game.is_playing());
     }
+
+    #[test]
+    fn undo_and_redo() {
+        let mut game = Game::start(Mark::O);
+
+        assert_eq!(game.undo(), None);
+
+        game.play((1, 1)).unwrap();
+        game.play((0, 0)).unwrap();
+        game.play((2, 2)).unwrap();
+
+        assert_eq!(game.undo(), Some(((2, 2), Mark::O)));
+        assert_eq!(game.undo(), Some(((0, 0), Mark::X)));
+        assert_eq!(game.turn(), Mark::X);
+        assert_eq!(game.history(), &[((1, 1), Mark::O)]);
+        assert_eq!(game.grid().cells().filter(|cell| cell.is_some()).count(), 1);
+
+        assert_eq!(game.redo(), Some(((0, 0), Mark::X)));
+        assert_eq!(game.turn(), Mark::O);
+
+        // Playing a new move forgets the moves that were taken back
+        game.play((0, 2)).unwrap();
+
+        assert_eq!(game.redo(), None);
+        assert_eq!(game.moves_played(), 3);
+    }
+
+    #[test]
+    fn undo_all_the_way_back() {
+        let mut game = Game::start(Mark::X);
+
+        game.play((1, 1)).unwrap();
+        game.play((0, 0)).unwrap();
+
+        while game.undo().is_some() {}
+
+        assert_eq!(game.turn(), Mark::X);
+        assert_eq!(game.moves_played(), 0);
+        assert_eq!(game.grid().last_mark(), None);
+        assert!(game.grid().is_unmarked_at((1, 1)));
+    }
+
+    #[test]
+    fn redo_a_winning_move() {
+        let mut game = Game::start(Mark::X);
+
+        for &pos in &[(0, 0), (1, 0), (0, 1), (1, 1), (0, 2)] {
+            game.play(pos).unwrap();
+        }
+
+        game.undo();
+
+        assert!(game.is_playing());
+
+        game.redo();
+
+        assert_eq!(game.outcome(), Some(Outcome::Win(Mark::X)));
+        assert_eq!(game.turn(), Mark::X);
+    }
+
+    #[test]
+    fn restart_clears_the_history() {
+        let mut game = Game::start(Mark::X);
+
+        game.play((1, 1)).unwrap();
+        game.undo();
+        game.play((0, 0)).unwrap();
+        game.restart();
+
+        assert_eq!(game.moves_played(), 0);
+        assert_eq!(game.redo(), None);
+    }
+
+    #[test]
+    fn blocked_draw() {
+        let mut game = Game::start(Mark::X).with_draw_rule(DrawRule::Blocked);
+
+        for &pos in &[(1, 1), (0, 0), (2, 2), (0, 2), (0, 1), (2, 1), (1, 0), (1, 2)] {
+            assert!(game.is_playing());
+            game.play(pos).unwrap();
+        }
+
+        assert_eq!(game.outcome(), Some(Outcome::Draw));
+        assert!(game.grid().is_unmarked_at((2, 0)));
+
+        game.restart();
+
+        assert_eq!(game.draw_rule(), DrawRule::Blocked);
+    }
+
+    #[test]
+    fn a_position_can_be_drawn_by_the_draw_rule() {
+        let game = "xox/xoo/ox. x".parse::<Game>().unwrap();
+
+        assert!(game.is_playing());
+
+        let game = game.with_draw_rule(DrawRule::Blocked);
+
+        assert_eq!(game.outcome(), Some(Outcome::Draw));
+        assert_eq!(game.turn(), Mark::X);
+
+        // But the rule doesn't end a game that's still open
+        let game = "xox/xoo/... x".parse::<Game>().unwrap().with_draw_rule(DrawRule::Blocked);
+
+        assert!(game.is_playing());
+    }
+
+    #[test]
+    fn perfect_play_draw() {
+        let mut game = Game::start(Mark::X).with_draw_rule(DrawRule::PerfectPlay);
+
+        // Neither player can force a win after any opening
+        game.play((0, 1)).unwrap();
+
+        assert_eq!(game.outcome(), Some(Outcome::Draw));
+        assert_eq!(game.turn(), Mark::X);
+        assert_eq!(game.moves_played(), 1);
+    }
+
+    #[test]
+    fn a_position_can_be_drawn_with_perfect_play() {
+        // O only has to take the centre, yet every line is still open
+        let game = "x../.../... o".parse::<Game>().unwrap();
+        let game = game.with_draw_rule(DrawRule::PerfectPlay);
+
+        assert_eq!(game.outcome(), Some(Outcome::Draw));
+        assert_eq!(game.turn(), Mark::O);
+
+        // But not one where X can still force a win
+        let game = "xx./oo./... x".parse::<Game>().unwrap().with_draw_rule(DrawRule::PerfectPlay);
+
+        assert!(game.is_playing());
+
+        // And not the empty grid, which is left to be played
+        assert!(Game::start(Mark::X).with_draw_rule(DrawRule::PerfectPlay).is_playing());
+    }
+
+    #[test]
+    fn the_rules_of_a_position_can_be_set_in_any_order() {
+        let position = || ".x./.o./... x".parse::<Game>().unwrap();
+
+        // X can't force a win under the standard rules, but can under misère
+        assert!(position().with_draw_rule(DrawRule::PerfectPlay).is_game_over());
+        assert!(position().with_draw_rule(DrawRule::PerfectPlay).with_variant(Variant::Misere).is_playing());
+        assert!(position().with_variant(Variant::Misere).with_draw_rule(DrawRule::PerfectPlay).is_playing());
+    }
+
+    #[test]
+    fn perfect_play_draw_while_the_shared_solver_is_locked() {
+        let solver = ai::shared_solver();
+        let mut game = Game::start(Mark::X).with_draw_rule(DrawRule::PerfectPlay);
+
+        game.play((1, 1)).unwrap();
+
+        assert_eq!(game.outcome(), Some(Outcome::Draw));
+
+        drop(solver);
+    }
+
+    #[test]
+    fn perfect_play_draw_when_someone_can_force_a_win() {
+        // The first player can force a win on a 3x4 grid
+        let mut game = Game::start_with_shape(Mark::X, Shape::new(3, 4, 3)).with_draw_rule(DrawRule::PerfectPlay);
+
+        while game.is_playing() {
+            game.play(ai::moves(&game)[0]).unwrap();
+        }
+
+        assert_eq!(game.outcome(), Some(Outcome::Win(Mark::X)));
+    }
+
+    #[test]
+    fn perfect_play_draw_on_a_grid_too_large_to_solve() {
+        // Without the search, the game is only drawn once every line is blocked
+        let mut game = Game::start_with_shape(Mark::X, Shape::new(5, 5, 5)).with_draw_rule(DrawRule::PerfectPlay);
+
+        for &pos in &[(0, 1), (0, 3), (1, 2), (1, 4), (2, 3), (2, 0), (3, 4), (3, 1), (4, 0), (4, 2), (0, 0), (4, 4)] {
+            assert!(game.is_playing());
+            game.play(pos).unwrap();
+        }
+
+        assert_eq!(game.outcome(), Some(Outcome::Draw));
+    }
+
+    #[test]
+    fn restart_keeps_the_shape() {
+        let mut game = Game::start_with_shape(Mark::X, Shape::new(2, 5, 2));
+
+        game.play((0, 0)).unwrap();
+        game.play((1, 4)).unwrap();
+        game.play((0, 1)).unwrap();
+
+        assert_eq!(game.outcome(), Some(Outcome::Win(Mark::X)));
+
+        game.restart();
+
+        assert_eq!(game.grid().shape(), Shape::new(2, 5, 2));
+        assert_eq!(game.grid().unmarked_positions().count(), 10);
+    }
 }
@@ -1,7 +1,179 @@
+use std::error::Error;
+use std::fmt;
+
+use crate::bits::{ self, Bits };
 use crate::mark::Mark;
 
-const SIZE: usize = 3;
-const NCELLS: usize = SIZE * SIZE;
+/// The shape of a [`Grid`], i.e. its number of `rows` and `columns`, along with
+/// the number of marks, `k`, that a player needs to get in a row in order to win.
+///
+/// It describes the board of an [m,n,k-game]. Tic-tac-toe is the 3,3,3-game.
+///
+/// # Examples
+///
+/// ```
+/// use xsos::Shape;
+///
+/// // Tic-tac-toe
+/// let shape = Shape::default();
+///
+/// assert_eq!((shape.rows(), shape.columns(), shape.k()), (3, 3, 3));
+///
+/// // Gomoku
+/// let shape = Shape::new(15, 15, 5);
+///
+/// assert_eq!(shape.ncells(), 225);
+/// ```
+///
+/// [`Grid`]: ./struct.Grid.html
+/// [m,n,k-game]: https://en.wikipedia.org/wiki/M,n,k-game
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct Shape {
+    rows: usize,
+    columns: usize,
+    k: usize
+}
+
+impl Shape {
+    /// Creates a new `Shape` with the given number of `rows` and `columns`
+    /// and where `k` marks in a row are needed to win.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `rows` or `columns` is 0, or if `k` is not in the range `1..=max(rows, columns)`.
+    ///
+    /// Also panics if `rows * (columns + 1) > 256`, which is the limit on the size of a grid.
+    /// That allows for grids of up to 15x15.
+    ///
+    /// Use [`try_new`] to check the dimensions without panicking.
+    ///
+    /// [`try_new`]: #method.try_new
+    pub fn new(rows: usize, columns: usize, k: usize) -> Self {
+        Self::try_new(rows, columns, k).unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Creates a new `Shape` like [`new`], but returns a [`ShapeError`] instead of panicking
+    /// when there's no such `Shape`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use xsos::{ Shape, ShapeError };
+    ///
+    /// assert_eq!(Shape::try_new(4, 5, 4), Ok(Shape::new(4, 5, 4)));
+    /// assert_eq!(Shape::try_new(16, 16, 5), Err(ShapeError::TooLarge));
+    /// assert_eq!(Shape::try_new(3, 4, 5), Err(ShapeError::InvalidK));
+    /// ```
+    ///
+    /// [`new`]: #method.new
+    /// [`ShapeError`]: ./enum.ShapeError.html
+    pub fn try_new(rows: usize, columns: usize, k: usize) -> Result<Self, ShapeError> {
+        if rows == 0 || columns == 0 {
+            Err(ShapeError::Empty)
+        } else if rows * (columns + 1) > bits::CAPACITY {
+            Err(ShapeError::TooLarge)
+        } else if k == 0 || k > rows.max(columns) {
+            Err(ShapeError::InvalidK)
+        } else {
+            Ok(Self { rows, columns, k })
+        }
+    }
+
+    /// Returns the number of rows.
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// Returns the number of columns.
+    pub fn columns(&self) -> usize {
+        self.columns
+    }
+
+    /// Returns the number of marks in a row that are needed to win.
+    pub fn k(&self) -> usize {
+        self.k
+    }
+
+    /// Returns the number of cells, i.e. `rows * columns`.
+    pub fn ncells(&self) -> usize {
+        self.rows * self.columns
+    }
+
+    /// Returns `true` if the given `Position` is within the bounds of a [`Grid`] with this
+    /// `Shape`, i.e. `r < rows` and `c < columns`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use xsos::Shape;
+    ///
+    /// let shape = Shape::new(3, 4, 3);
+    ///
+    /// assert!(shape.in_bounds((0, 0)));
+    /// assert!(shape.in_bounds((2, 3)));
+    ///
+    /// assert!(!shape.in_bounds((3, 0)));
+    /// assert!(!shape.in_bounds((0, 4)));
+    /// ```
+    ///
+    /// [`Grid`]: ./struct.Grid.html
+    pub fn in_bounds(&self, (r, c): Position) -> bool {
+        r < self.rows && c < self.columns
+    }
+
+    /// Returns the distance between vertically adjacent cells in a bitboard.
+    ///
+    /// Each row is followed by an extra column that's never marked so that lines
+    /// can't wrap around from the end of one row to the start of the next.
+    pub(crate) fn stride(self) -> usize {
+        self.columns + 1
+    }
+
+    /// Returns the bit that represents the given `Position` in a bitboard.
+    pub(crate) fn to_bit(self, (r, c): Position) -> usize {
+        r * self.stride() + c
+    }
+
+    fn to_pos(self, index: usize) -> Position {
+        (index / self.columns, index % self.columns)
+    }
+}
+
+impl Default for Shape {
+    /// The 3x3 Tic-tac-toe shape where 3 in a row wins.
+    fn default() -> Self {
+        Self::new(3, 3, 3)
+    }
+}
+
+/// The possible errors that can occur when creating a [`Shape`].
+///
+/// [`Shape`]: ./struct.Shape.html
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ShapeError {
+    /// There were no rows or no columns.
+    Empty,
+
+    /// There were more cells than a [`Grid`] can hold, i.e. `rows * (columns + 1) > 256`.
+    ///
+    /// [`Grid`]: ./struct.Grid.html
+    TooLarge,
+
+    /// The `k` wasn't between 1 and the length of the longest side.
+    InvalidK
+}
+
+impl fmt::Display for ShapeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => write!(f, "a grid must have at least one row and one column"),
+            Self::TooLarge => write!(f, "the grid is too large"),
+            Self::InvalidK => write!(f, "k must be between 1 and the length of the longest side")
+        }
+    }
+}
+
+impl Error for ShapeError {}
 
 /// The location of a [`Cell`] within a [`Grid`].
 ///
@@ -37,7 +209,9 @@ pub type Position = (usize, usize);
 /// [`Mark`]: ./enum.Mark.html
 pub type Cell = Option<Mark>;
 
-/// A 3x3 Tic-tac-toe grid.
+/// A Tic-tac-toe grid.
+///
+/// By default it's a 3x3 grid but any [`Shape`] is supported.
 ///
 /// <pre>
 ///   0   1   2
@@ -47,20 +221,50 @@ pub type Cell = Option<Mark>;
 ///  ---+---+---
 /// 2   |   |
 /// </pre>
+///
+/// [`Shape`]: ./struct.Shape.html
 #[derive(Clone)]
 pub struct Grid {
-    cells: [Cell; NCELLS],
+    shape: Shape,
+    xs: Bits,
+    os: Bits,
     last: Option<Mark>
 }
 
 impl Grid {
-    /// Creates a new empty `Grid`.
+    /// Creates a new empty 3x3 `Grid`.
     pub fn new() -> Self {
-        Self { cells: [None; NCELLS], last: None }
+        Self::with_shape(Shape::default())
+    }
+
+    /// Creates a new empty `Grid` with the given [`Shape`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use xsos::{ Grid, Shape };
+    ///
+    /// let grid = Grid::with_shape(Shape::new(4, 5, 4));
+    ///
+    /// assert_eq!(grid.unmarked_positions().count(), 20);
+    /// ```
+    ///
+    /// [`Shape`]: ./struct.Shape.html
+    pub fn with_shape(shape: Shape) -> Self {
+        Self { shape, xs: Bits::default(), os: Bits::default(), last: None }
+    }
+
+    /// Returns the [`Shape`] of this `Grid`.
+    ///
+    /// [`Shape`]: ./struct.Shape.html
+    pub fn shape(&self) -> Shape {
+        self.shape
     }
 
     /// Returns `true` if the given `Position` is within the bounds of a 3x3 grid, i.e. `r ∊ {0, 1, 2}` and `c ∊ {0, 1, 2}`.
     ///
+    /// Use [`Shape::in_bounds`] for a grid of any other shape.
+    ///
     /// # Examples
     ///
     /// ```
@@ -72,8 +276,10 @@ impl Grid {
     /// assert!(!Grid::in_bounds((3, 3)));
     /// assert!(!Grid::in_bounds((0, 3)));
     /// ```
-    pub fn in_bounds((r, c): Position) -> bool {
-        r < SIZE && c < SIZE
+    ///
+    /// [`Shape::in_bounds`]: ./struct.Shape.html#method.in_bounds
+    pub fn in_bounds(p: Position) -> bool {
+        Shape::default().in_bounds(p)
     }
 
     /// Marks the [`Cell`] at the given `Position` on this `Grid` with a `Mark`.
@@ -97,19 +303,49 @@ impl Grid {
     ///
     /// # Panics
     ///
-    /// Panics if `Grid::in_bounds(p)` is `false`.
+    /// Panics if `self.shape().in_bounds(p)` is `false`.
     ///
     /// [`Cell`]: ./type.Cell.html
     pub fn mark(&mut self, p: Position, m: Mark) {
-        self.cells[to_index(p)] = Some(m);
+        let i = self.to_bit(p);
+
+        match m {
+            Mark::X => {
+                self.xs.set(i);
+                self.os.clear(i);
+            },
+            Mark::O => {
+                self.os.set(i);
+                self.xs.clear(i);
+            }
+        }
+
         self.last = Some(m);
     }
 
+    /// Clears the [`Cell`] at the given `Position` and sets the last `Mark` back to `last`.
+    ///
+    /// It's the inverse of [`mark`], used to take back moves.
+    ///
+    /// [`Cell`]: ./type.Cell.html
+    /// [`mark`]: #method.mark
+    pub(crate) fn unmark(&mut self, p: Position, last: Option<Mark>) {
+        let i = self.to_bit(p);
+
+        self.xs.clear(i);
+        self.os.clear(i);
+        self.last = last;
+    }
+
+    pub(crate) fn set_last_mark(&mut self, last: Option<Mark>) {
+        self.last = last;
+    }
+
     /// Returns `true` if the [`Cell`] at the given `Position` is marked.
     ///
     /// # Panics
     ///
-    /// Panics if `Grid::in_bounds(p)` is `false`.
+    /// Panics if `self.shape().in_bounds(p)` is `false`.
     ///
     /// [`Cell`]: ./type.Cell.html
     pub fn is_marked_at(&self, p: Position) -> bool {
@@ -120,11 +356,43 @@ impl Grid {
     ///
     /// # Panics
     ///
-    /// Panics if `Grid::in_bounds(p)` is `false`.
+    /// Panics if `self.shape().in_bounds(p)` is `false`.
     ///
     /// [`Cell`]: ./type.Cell.html
     pub fn is_unmarked_at(&self, p: Position) -> bool {
-        self.cells[to_index(p)].is_none()
+        self.cell_at(p).is_none()
+    }
+
+    /// Returns the [`Cell`] at the given `Position`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use xsos::{ Grid, Mark };
+    ///
+    /// let mut grid = Grid::new();
+    ///
+    /// grid.mark((2, 1), Mark::O);
+    ///
+    /// assert_eq!(grid.cell_at((2, 1)), Some(Mark::O));
+    /// assert_eq!(grid.cell_at((1, 2)), None);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `self.shape().in_bounds(p)` is `false`.
+    ///
+    /// [`Cell`]: ./type.Cell.html
+    pub fn cell_at(&self, p: Position) -> Cell {
+        let i = self.to_bit(p);
+
+        if self.xs.get(i) {
+            Some(Mark::X)
+        } else if self.os.get(i) {
+            Some(Mark::O)
+        } else {
+            None
+        }
     }
 
     /// Returns the last `Mark`, if any, to be marked on a [`Cell`].
@@ -159,8 +427,8 @@ impl Grid {
     /// ```
     ///
     /// [row-major order]: https://en.wikipedia.org/wiki/Row-_and_column-major_order
-    pub fn unmarked_positions(&self) -> UnmarkedPositions {
-        UnmarkedPositions::new(&self.cells)
+    pub fn unmarked_positions(&self) -> UnmarkedPositions<'_> {
+        UnmarkedPositions::new(self)
     }
 
     /// Returns an iterator over the cells in this `Grid`.
@@ -192,8 +460,22 @@ impl Grid {
     /// ```
     ///
     /// [row-major order]: https://en.wikipedia.org/wiki/Row-_and_column-major_order
-    pub fn cells(&self) -> Cells {
-        Cells::new(&self.cells)
+    pub fn cells(&self) -> Cells<'_> {
+        Cells::new(self)
+    }
+
+    /// Returns the bitboard of the cells that are marked with the given `Mark`.
+    pub(crate) fn bits(&self, m: Mark) -> Bits {
+        match m {
+            Mark::X => self.xs,
+            Mark::O => self.os
+        }
+    }
+
+    fn to_bit(&self, p: Position) -> usize {
+        assert!(self.shape.in_bounds(p), "position out of bounds");
+
+        self.shape.to_bit(p)
     }
 }
 
@@ -205,13 +487,13 @@ impl Grid {
 /// [`unmarked_positions`]: ./struct.Grid.html#method.unmarked_positions
 /// [`Grid`]: ./struct.Grid.html
 pub struct UnmarkedPositions<'a> {
-    cells: &'a [Cell],
+    grid: &'a Grid,
     index: usize
 }
 
 impl<'a> UnmarkedPositions<'a> {
-    fn new(cells: &'a [Cell]) -> Self {
-        Self { cells, index: 0 }
+    fn new(grid: &'a Grid) -> Self {
+        Self { grid, index: 0 }
     }
 }
 
@@ -219,15 +501,17 @@ impl Iterator for UnmarkedPositions<'_> {
     type Item = Position;
 
     fn next(&mut self) -> Option<Self::Item> {
-        while self.index < NCELLS && self.cells[self.index].is_some() {
+        let shape = self.grid.shape;
+
+        while self.index < shape.ncells() && self.grid.is_marked_at(shape.to_pos(self.index)) {
             self.index += 1;
         }
 
-        if self.index == NCELLS {
+        if self.index == shape.ncells() {
             None
         } else {
             self.index += 1;
-            Some(to_pos(self.index - 1))
+            Some(shape.to_pos(self.index - 1))
         }
     }
 }
@@ -237,16 +521,21 @@ impl Iterator for UnmarkedPositions<'_> {
 /// This struct is created by the [`cells`] method on [`Grid`].
 /// See its documentation for more.
 ///
+/// It yields references, as it did when a `Grid` stored an array of cells, so that code written
+/// against that stays the same. Since the cells are now worked out from the bitboards, each
+/// reference is to one of the three constant cells, which lives for `'static` and so for as long
+/// as the `Grid` too.
+///
 /// [`cells`]: ./struct.Grid.html#method.cells
 /// [`Grid`]: ./struct.Grid.html
 pub struct Cells<'a> {
-    cells: &'a [Cell],
+    grid: &'a Grid,
     index: usize
 }
 
 impl<'a> Cells<'a> {
-    fn new(cells: &'a [Cell]) -> Self {
-        Self { cells, index: 0 }
+    fn new(grid: &'a Grid) -> Self {
+        Self { grid, index: 0 }
     }
 }
 
@@ -254,21 +543,27 @@ impl<'a> Iterator for Cells<'a> {
     type Item = &'a Cell;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.index < NCELLS {
+        let shape = self.grid.shape;
+
+        if self.index < shape.ncells() {
             self.index += 1;
-            Some(&self.cells[self.index - 1])
+
+            // Each of these is a constant, so the reference to it is promoted to `'static`
+            match self.grid.cell_at(shape.to_pos(self.index - 1)) {
+                Some(Mark::X) => Some(&Some(Mark::X)),
+                Some(Mark::O) => Some(&Some(Mark::O)),
+                None => Some(&None)
+            }
         } else {
             None
         }
     }
 }
 
-fn to_index((r, c): Position) -> usize {
-    r * SIZE + c
-}
-
-fn to_pos(index: usize) -> Position {
-    (index / SIZE, index % SIZE)
+impl Default for Grid {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 #[cfg(test)]
@@ -312,4 +607,79 @@ mod tests {
         assert!(clone_of_grid.is_marked_at((1, 1)));
         assert!(grid.is_unmarked_at((1, 1)));
     }
+
+    #[test]
+    fn rectangular() {
+        let mut grid = Grid::with_shape(Shape::new(2, 4, 2));
+
+        grid.mark((0, 3), Mark::X);
+        grid.mark((1, 0), Mark::O);
+
+        assert!(grid.shape().in_bounds((1, 3)));
+        assert!(!grid.shape().in_bounds((2, 0)));
+        assert!(!grid.shape().in_bounds((0, 4)));
+
+        assert_eq!(grid.unmarked_positions().collect::<Vec<_>>(), vec![
+            (0, 0), (0, 1), (0, 2),
+            (1, 1), (1, 2), (1, 3)
+        ]);
+
+        assert_eq!(grid.cells().collect::<Vec<_>>(), vec![
+            &None, &None, &None, &Some(Mark::X),
+            &Some(Mark::O), &None, &None, &None
+        ]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn k_longer_than_the_grid() {
+        Shape::new(3, 4, 5);
+    }
+
+    #[test]
+    #[should_panic]
+    fn grid_too_large() {
+        Shape::new(16, 16, 5);
+    }
+
+    #[test]
+    fn invalid_shapes() {
+        assert_eq!(Shape::try_new(0, 3, 1), Err(ShapeError::Empty));
+        assert_eq!(Shape::try_new(3, 0, 1), Err(ShapeError::Empty));
+        assert_eq!(Shape::try_new(3, 3, 0), Err(ShapeError::InvalidK));
+        assert_eq!(Shape::try_new(15, 15, 5), Ok(Shape::new(15, 15, 5)));
+        assert_eq!(Shape::try_new(15, 16, 5), Ok(Shape::new(15, 16, 5)));
+        assert_eq!(Shape::try_new(15, 17, 5), Err(ShapeError::TooLarge));
+    }
+
+    #[test]
+    #[should_panic]
+    fn out_of_bounds() {
+        // On a 3x3 grid (0, 3) would otherwise be the padding bit after the first row
+        Grid::new().mark((0, 3), Mark::X);
+    }
+
+    #[test]
+    fn bitboards() {
+        let mut grid = Grid::with_shape(Shape::new(2, 3, 2));
+
+        grid.mark((0, 2), Mark::X);
+        grid.mark((1, 0), Mark::O);
+        grid.mark((1, 1), Mark::X);
+
+        // Rows are 4 bits apart
+        assert_eq!(grid.bits(Mark::X), Bits::bit(2) | Bits::bit(5));
+        assert_eq!(grid.bits(Mark::O), Bits::bit(4));
+
+        // Re-marking replaces the mark
+        grid.mark((1, 0), Mark::X);
+
+        assert_eq!(grid.bits(Mark::X), Bits::bit(2) | Bits::bit(4) | Bits::bit(5));
+        assert!(grid.bits(Mark::O).is_empty());
+
+        grid.unmark((0, 2), Some(Mark::O));
+
+        assert!(grid.is_unmarked_at((0, 2)));
+        assert_eq!(grid.last_mark(), Some(Mark::O));
+    }
 }
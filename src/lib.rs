@@ -1,12 +1,29 @@
 pub mod ai;
+mod bits;
 mod game;
 mod grid;
 mod mark;
+mod notation;
+mod notakto;
+mod qubic;
+mod record;
 mod referee;
+#[cfg(feature = "serde")]
+mod serialization;
+mod strategy;
+mod ultimate;
+mod wild;
 
-pub use game::{ PlayError, Game };
-pub use grid::{ Cell, Cells, Grid, Position, UnmarkedPositions };
+pub use game::{ BoardPlayError, MoveOutcome, PlayError, Game };
+pub use grid::{ Cell, Cells, Grid, Position, Shape, ShapeError, UnmarkedPositions };
 pub use mark::Mark;
-pub use referee::Outcome;
+pub use notation::NotationError;
+pub use notakto::{ NotaktoGame, NotaktoMove };
+pub use qubic::{ QubicGame, QubicPosition };
+pub use record::{ Record, RecordError };
+pub use referee::{ DrawRule, Outcome, Variant };
+pub use strategy::{ Blundering, Difficulty, FirstUnmarked, Lookahead, Perfect, Random, Strategy };
+pub use ultimate::{ UltimateGame, UltimateMove };
+pub use wild::{ WildGame, WildMove };
 
 pub mod cli;
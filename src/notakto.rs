@@ -0,0 +1,233 @@
+use crate::game::{ self, BoardPlayError, MoveOutcome, PlayError };
+use crate::grid::{ Grid, Position };
+use crate::mark::Mark;
+use crate::referee::{ self, Outcome };
+
+/// A move in a [`NotaktoGame`], given by the index of a board and the position of a cell on it.
+///
+/// [`NotaktoGame`]: ./struct.NotaktoGame.html
+pub type NotaktoMove = (usize, Position);
+
+/// The game logic for Notakto.
+///
+/// It's played on a number of 3x3 boards, where both players mark X's, on any board that's still
+/// alive. A board dies as soon as it has three X's in a line, and whoever kills the last board
+/// loses. So there are no draws.
+///
+/// # Examples
+///
+/// ```
+/// use xsos::{ BoardPlayError, Mark, MoveOutcome, NotaktoGame, Outcome };
+///
+/// # fn main() -> Result<(), BoardPlayError> {
+/// let mut game = NotaktoGame::start_with_boards(Mark::X, 2);
+///
+/// // The players fill in the top row of the first board, so X kills it
+/// game.play(0, (0, 0))?;
+/// game.play(0, (0, 1))?;
+/// game.play(0, (0, 2))?;
+///
+/// assert!(game.is_dead(0));
+/// assert_eq!(game.play(0, (1, 1)), Err(BoardPlayError::WrongBoard));
+///
+/// // That leaves the second board, and O is the one to kill it
+/// game.play(1, (1, 1))?;
+/// game.play(1, (0, 0))?;
+///
+/// assert_eq!(game.play(1, (2, 2))?, MoveOutcome::GameOver(Outcome::Win(Mark::X)));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct NotaktoGame {
+    boards: Vec<Grid>,
+    turn: Mark,
+    outcome: Option<Outcome>,
+    history: Vec<(NotaktoMove, Mark)>
+}
+
+impl NotaktoGame {
+    /// Start a new game on a single board and let `first` play first.
+    pub fn start(first: Mark) -> Self {
+        Self::start_with_boards(first, 1)
+    }
+
+    /// Start a new game on the given number of `boards` and let `first` play first.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `boards` is 0.
+    pub fn start_with_boards(first: Mark, boards: usize) -> Self {
+        assert!(boards > 0, "there must be at least one board");
+
+        Self { boards: vec![Grid::new(); boards], turn: first, outcome: None, history: Vec::new() }
+    }
+
+    /// Restart a game with the same number of boards, all of them empty. Notakto can't be drawn,
+    /// so the new game is started by the winner, the player who didn't kill the last board.
+    pub fn restart(&mut self) {
+        *self = Self::start_with_boards(game::first_turn(self.turn, self.outcome), self.boards.len());
+    }
+
+    /// Marks an X on the `cell` of the given `board`, on behalf of the player given by
+    /// `self.turn()`, unless it's game over.
+    ///
+    /// It returns:
+    ///
+    /// - `Err(BoardPlayError::Play(PlayError::GameOver))`, if this `NotaktoGame` is over.
+    /// - `Err(BoardPlayError::Play(PlayError::OutOfBounds))`, if there's no such `board`, or `cell`
+    ///   is out of bounds.
+    /// - `Err(BoardPlayError::WrongBoard)`, if the `board` is dead.
+    /// - `Err(BoardPlayError::Play(PlayError::AlreadyMarked))`, if the `cell` is already marked.
+    /// - `Ok(MoveOutcome::GameOver(outcome))`, if the move ended this `NotaktoGame`.
+    /// - `Ok(MoveOutcome::InPlay)`, otherwise.
+    pub fn play(&mut self, board: usize, cell: Position) -> Result<MoveOutcome, BoardPlayError> {
+        if self.is_game_over() {
+            Err(BoardPlayError::Play(PlayError::GameOver))
+        } else if board >= self.boards.len() || !self.boards[board].shape().in_bounds(cell) {
+            Err(BoardPlayError::Play(PlayError::OutOfBounds))
+        } else if self.is_dead(board) {
+            Err(BoardPlayError::WrongBoard)
+        } else if self.boards[board].is_marked_at(cell) {
+            Err(BoardPlayError::Play(PlayError::AlreadyMarked))
+        } else {
+            self.boards[board].mark(cell, Mark::X);
+            self.history.push(((board, cell), self.turn));
+
+            if (0..self.boards.len()).all(|i| self.is_dead(i)) {
+                self.outcome = Some(Outcome::Win(self.turn.swap()));
+            }
+
+            self.turn = game::next_turn(self.turn, self.outcome);
+
+            match self.outcome {
+                Some(outcome) => Ok(MoveOutcome::GameOver(outcome)),
+                None => Ok(MoveOutcome::InPlay)
+            }
+        }
+    }
+
+    /// Takes back the last move, if any, and returns it along with the player who played it.
+    pub fn undo(&mut self) -> Option<(NotaktoMove, Mark)> {
+        let ((board, cell), player) = self.history.pop()?;
+        let grid = &mut self.boards[board];
+
+        grid.unmark(cell, None);
+
+        if grid.cells().any(Option::is_some) {
+            grid.set_last_mark(Some(Mark::X));
+        }
+
+        self.turn = player;
+        self.outcome = None;
+
+        Some(((board, cell), player))
+    }
+
+    /// Returns the boards, which are 3x3 [`Grid`]s that only ever have X's on them.
+    ///
+    /// [`Grid`]: ./struct.Grid.html
+    pub fn boards(&self) -> &[Grid] {
+        &self.boards
+    }
+
+    /// Returns `true` if the given `board` has three X's in a line.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there's no such `board`.
+    pub fn is_dead(&self, board: usize) -> bool {
+        referee::is_win(&self.boards[board])
+    }
+
+    /// Returns every move that can be played next, i.e. the unmarked cells of the boards that are
+    /// still alive, board by board and in row-major order. Once the game is over there are none.
+    pub fn legal_moves(&self) -> Vec<NotaktoMove> {
+        (0..self.boards.len())
+            .filter(|&i| !self.is_dead(i))
+            .flat_map(|i| self.boards[i].unmarked_positions().map(move |p| (i, p)))
+            .collect()
+    }
+
+    /// Returns the moves played so far, in the order they were played, along with the players
+    /// who played them.
+    pub fn history(&self) -> &[(NotaktoMove, Mark)] {
+        &self.history
+    }
+
+    /// Returns `true` if this `NotaktoGame` is in play.
+    pub fn is_playing(&self) -> bool {
+        self.outcome.is_none()
+    }
+
+    /// Returns `true` if this `NotaktoGame` is over.
+    pub fn is_game_over(&self) -> bool {
+        !self.is_playing()
+    }
+
+    /// Returns the answer to the question "Who's turn is it to play?".
+    pub fn turn(&self) -> Mark {
+        self.turn
+    }
+
+    /// Returns a reason for why this `NotaktoGame` is over, if it is over. Otherwise it returns `None`.
+    pub fn outcome(&self) -> Option<Outcome> {
+        self.outcome
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn killing_the_last_board_loses() {
+        let mut game = NotaktoGame::start(Mark::O);
+
+        for &cell in &[(0, 0), (1, 1), (0, 2), (1, 0)] {
+            game.play(0, cell).unwrap();
+        }
+
+        assert_eq!(game.legal_moves().len(), 5);
+        assert_eq!(game.play(0, (0, 1)), Ok(MoveOutcome::GameOver(Outcome::Win(Mark::X))));
+        assert_eq!(game.turn(), Mark::O);
+        assert!(game.legal_moves().is_empty());
+        assert_eq!(game.play(0, (2, 2)), Err(BoardPlayError::Play(PlayError::GameOver)));
+
+        game.restart();
+
+        assert_eq!(game.turn(), Mark::X);
+        assert!(game.boards()[0].cells().all(Option::is_none));
+    }
+
+    #[test]
+    fn invalid_moves() {
+        let mut game = NotaktoGame::start_with_boards(Mark::X, 3);
+
+        assert_eq!(game.play(3, (0, 0)), Err(BoardPlayError::Play(PlayError::OutOfBounds)));
+        assert_eq!(game.play(2, (0, 3)), Err(BoardPlayError::Play(PlayError::OutOfBounds)));
+        assert_eq!(game.play(2, (1, 1)), Ok(MoveOutcome::InPlay));
+        assert_eq!(game.play(2, (1, 1)), Err(BoardPlayError::Play(PlayError::AlreadyMarked)));
+        assert_eq!(game.legal_moves().len(), 26);
+    }
+
+    #[test]
+    fn undo_brings_a_board_back_to_life() {
+        let mut game = NotaktoGame::start_with_boards(Mark::X, 2);
+
+        for &cell in &[(0, 1), (1, 1), (2, 1)] {
+            game.play(1, cell).unwrap();
+        }
+
+        assert!(game.is_dead(1));
+        assert_eq!(game.undo(), Some(((1, (2, 1)), Mark::X)));
+        assert!(!game.is_dead(1));
+        assert_eq!(game.turn(), Mark::X);
+
+        game.undo();
+        game.undo();
+
+        assert_eq!(game.boards()[1].last_mark(), None);
+        assert_eq!(game.undo(), None);
+    }
+}
@@ -0,0 +1,452 @@
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::game::Game;
+use crate::grid::{ Grid, Shape, ShapeError };
+use crate::mark::Mark;
+use crate::referee;
+
+/// The possible errors that can occur when parsing a [`Grid`] or a [`Game`] from its notation.
+///
+/// [`Game`]: ./struct.Game.html
+/// [`Grid`]: ./struct.Grid.html
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum NotationError {
+    /// There were no cells.
+    MissingCells,
+
+    /// There was no mark for the player whose turn it is.
+    MissingTurn,
+
+    /// There was something left over after the notation.
+    UnexpectedInput(String),
+
+    /// A cell wasn't one of `x`, `o` or `.`.
+    InvalidCell(char),
+
+    /// The rows weren't all the same length.
+    UnevenRows,
+
+    /// The grid was larger than any [`Shape`] allows.
+    ///
+    /// [`Shape`]: ./struct.Shape.html
+    TooLarge,
+
+    /// The `k` wasn't a number between 1 and the length of the longest side of the grid.
+    InvalidK(String),
+
+    /// The mark for the player whose turn it is wasn't one of `x` or `o`.
+    InvalidTurn(String),
+
+    /// The players' marks can't differ in number by more than one.
+    WrongMarkCounts { xs: usize, os: usize },
+
+    /// Both players completed a line.
+    BothWon,
+
+    /// It can't be the given player's turn.
+    WrongTurn(Mark),
+
+    /// The given player completed lines that no single move could have completed together.
+    ImpossibleWin(Mark)
+}
+
+impl fmt::Display for NotationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingCells => write!(f, "expected the cells of a grid"),
+            Self::MissingTurn => write!(f, "expected x or o for the player whose turn it is"),
+            Self::UnexpectedInput(input) => write!(f, "unexpected \"{}\"", input),
+            Self::InvalidCell(c) => write!(f, "expected x, o or . for a cell but got '{}'", c),
+            Self::UnevenRows => write!(f, "the rows are not all the same length"),
+            Self::TooLarge => write!(f, "the grid is too large"),
+            Self::InvalidK(k) => write!(f, "expected k to be between 1 and the length of the longest side but got \"{}\"", k),
+            Self::InvalidTurn(turn) => write!(f, "expected x or o for the player whose turn it is but got \"{}\"", turn),
+            Self::WrongMarkCounts { xs, os } => write!(f, "there are {} x's and {} o's but they can differ by at most one", xs, os),
+            Self::BothWon => write!(f, "both x and o have completed a line"),
+            Self::WrongTurn(mark) => write!(f, "it can't be {}'s turn", mark),
+            Self::ImpossibleWin(mark) => write!(f, "{} has completed lines that no single move could have completed", mark)
+        }
+    }
+}
+
+impl Error for NotationError {}
+
+/// Parses a `Grid` from its notation.
+///
+/// A `Grid` is written row by row, top to bottom, with the rows separated by `/` and each cell
+/// written as `x`, `o` or `.` for an unmarked cell. If `k` isn't the length of the shortest side
+/// then it follows after a space. On a grid with a single row or column, where that would make
+/// every mark a line, the `k` that's left out is the length of the row or column instead.
+///
+/// The last mark of the grid is the one there are more of. With as many x's as o's it's the mark
+/// that completed a line, since whoever did must have moved last, or `None` if neither did.
+///
+/// # Examples
+///
+/// ```
+/// use xsos::{ Grid, Mark, NotationError };
+///
+/// let grid = "x.o/.x./o..".parse::<Grid>().unwrap();
+///
+/// assert_eq!(grid.cell_at((0, 2)), Some(Mark::O));
+/// assert_eq!(grid.to_string(), "x.o/.x./o..");
+///
+/// // Only 4 in a row are needed on this 5x5 grid
+/// let grid = "...../...../...../...../..... 4".parse::<Grid>().unwrap();
+///
+/// assert_eq!(grid.shape().k(), 4);
+///
+/// // O completed the middle row, so O made the last mark
+/// let grid = "xx./ooo/x..".parse::<Grid>().unwrap();
+///
+/// assert_eq!(grid.last_mark(), Some(Mark::O));
+///
+/// // A single row needs 3 in a row too
+/// assert_eq!("...".parse::<Grid>().unwrap().shape().k(), 3);
+///
+/// assert_eq!("xx./.../...".parse::<Grid>().err(), Some(NotationError::WrongMarkCounts { xs: 2, os: 0 }));
+/// ```
+impl FromStr for Grid {
+    type Err = NotationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut tokens = s.split_whitespace();
+        let cells = tokens.next().ok_or(NotationError::MissingCells)?;
+        let k = tokens.next();
+
+        expect_end(tokens)?;
+
+        let grid = parse_grid(cells, k)?;
+        let (xs, os) = counts(&grid)?;
+
+        if has_won(&grid, Mark::X) && has_won(&grid, Mark::O) {
+            return Err(NotationError::BothWon);
+        }
+
+        let last = match xs.cmp(&os) {
+            std::cmp::Ordering::Greater => Some(Mark::X),
+            std::cmp::Ordering::Less => Some(Mark::O),
+            // Whoever completed a line must have made the last mark
+            std::cmp::Ordering::Equal => [Mark::X, Mark::O].iter().cloned().find(|&m| has_won(&grid, m))
+        };
+
+        Ok(with_last_mark(grid, last))
+    }
+}
+
+impl fmt::Display for Grid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", format_cells(self))?;
+
+        let shape = self.shape();
+
+        if shape.k() != default_k(shape.rows(), shape.columns()) {
+            write!(f, " {}", shape.k())?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Parses a `Game` from its notation.
+///
+/// A `Game` is written as its [`Grid`], `k` included, followed by the [`Mark`] whose turn it is,
+/// i.e. the player to move or, once the game is over, the player who made the final move. So for
+/// e.g. `x.o/.x./o.. o` is a game of Tic-tac-toe in which it's O's turn to block X's diagonal and
+/// `..../..../..../.... 3 x` is the start of a game on a 4x4 grid that needs 3 in a row.
+///
+/// The game starts out with no history, so the moves that led to the position can't be undone.
+///
+/// # Examples
+///
+/// ```
+/// use xsos::{ Game, Mark, NotationError, Outcome };
+///
+/// let mut game = "x.o/.x./o.. x".parse::<Game>().unwrap();
+///
+/// assert_eq!(game.turn(), Mark::X);
+///
+/// game.play((2, 2)).unwrap();
+///
+/// assert_eq!(game.outcome(), Some(Outcome::Win(Mark::X)));
+/// assert_eq!(game.to_string(), "x.o/.x./o.x x");
+///
+/// // X has made one more mark than O, so it has to be O's turn
+/// assert_eq!("x.o/.x./... x".parse::<Game>().err(), Some(NotationError::WrongTurn(Mark::X)));
+/// ```
+///
+/// [`Grid`]: ./struct.Grid.html
+/// [`Mark`]: ./enum.Mark.html
+impl FromStr for Game {
+    type Err = NotationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut tokens = s.split_whitespace();
+        let cells = tokens.next().ok_or(NotationError::MissingCells)?;
+
+        // As in a grid, the k is only there when it isn't the default
+        let (k, turn) = match (tokens.next(), tokens.next()) {
+            (None, _) => return Err(NotationError::MissingTurn),
+            (Some(turn), None) => (None, turn),
+            (Some(k), Some(turn)) => (Some(k), turn)
+        };
+
+        expect_end(tokens)?;
+
+        let grid = parse_grid(cells, k)?;
+        let turn = match turn.to_ascii_lowercase().as_ref() {
+            "x" => Mark::X,
+            "o" => Mark::O,
+            _ => return Err(NotationError::InvalidTurn(turn.to_owned()))
+        };
+        let (xs, os) = counts(&grid)?;
+
+        let winners = [Mark::X, Mark::O].iter().cloned().filter(|&m| has_won(&grid, m)).collect::<Vec<_>>();
+        let is_over = match winners[..] {
+            [] => grid.unmarked_positions().next().is_none(),
+            [winner] => {
+                if !could_be_won_in_one_move(&grid, winner) {
+                    return Err(NotationError::ImpossibleWin(winner));
+                }
+
+                if turn != winner {
+                    return Err(NotationError::WrongTurn(turn));
+                }
+
+                true
+            },
+            _ => return Err(NotationError::BothWon)
+        };
+
+        // Once the game is over it's still the turn of the player who made the final move
+        let last = if is_over { turn } else { turn.swap() };
+        let is_consistent = match xs.cmp(&os) {
+            std::cmp::Ordering::Greater => last == Mark::X,
+            std::cmp::Ordering::Less => last == Mark::O,
+            std::cmp::Ordering::Equal => true
+        };
+
+        if !is_consistent {
+            return Err(NotationError::WrongTurn(turn));
+        }
+
+        let last = if xs + os > 0 { Some(last) } else { None };
+
+        Ok(Game::from_position(with_last_mark(grid, last), turn))
+    }
+}
+
+impl fmt::Display for Game {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.grid(), self.turn())
+    }
+}
+
+fn expect_end<'a>(mut tokens: impl Iterator<Item = &'a str>) -> Result<(), NotationError> {
+    match tokens.next() {
+        Some(token) => Err(NotationError::UnexpectedInput(token.to_owned())),
+        None => Ok(())
+    }
+}
+
+fn parse_grid(cells: &str, k: Option<&str>) -> Result<Grid, NotationError> {
+    let rows = cells
+        .split('/')
+        .map(|row| {
+            row.chars()
+                .map(|c| match c.to_ascii_lowercase() {
+                    'x' => Ok(Some(Mark::X)),
+                    'o' => Ok(Some(Mark::O)),
+                    '.' => Ok(None),
+                    _ => Err(NotationError::InvalidCell(c))
+                })
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let columns = rows[0].len();
+
+    if columns == 0 || rows.iter().any(|row| row.len() != columns) {
+        return Err(NotationError::UnevenRows);
+    }
+
+    let shape = match k {
+        Some(k) => k
+            .parse::<usize>()
+            .map_err(|_| ShapeError::InvalidK)
+            .and_then(|n| Shape::try_new(rows.len(), columns, n)),
+        None => Shape::try_new(rows.len(), columns, default_k(rows.len(), columns))
+    };
+    let shape = shape.map_err(|error| match error {
+        ShapeError::InvalidK => NotationError::InvalidK(k.unwrap_or_default().to_owned()),
+        _ => NotationError::TooLarge
+    })?;
+
+    let mut grid = Grid::with_shape(shape);
+
+    for (r, row) in rows.iter().enumerate() {
+        for (c, cell) in row.iter().enumerate() {
+            if let Some(mark) = *cell {
+                grid.mark((r, c), mark);
+            }
+        }
+    }
+
+    Ok(grid)
+}
+
+fn format_cells(grid: &Grid) -> String {
+    let shape = grid.shape();
+
+    (0..shape.rows())
+        .map(|r| {
+            (0..shape.columns())
+                .map(|c| match grid.cell_at((r, c)) {
+                    Some(mark) => mark.to_string(),
+                    None => String::from(".")
+                })
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// The `k` used when none is given, which is the length of the shortest side so that
+/// on a square grid a line has to go all the way across. On a single row or column it's
+/// the length of the longest side instead, since a line of 1 would be won by any mark.
+fn default_k(rows: usize, columns: usize) -> usize {
+    if rows == 1 || columns == 1 {
+        rows.max(columns)
+    } else {
+        rows.min(columns)
+    }
+}
+
+fn counts(grid: &Grid) -> Result<(usize, usize), NotationError> {
+    let xs = grid.cells().filter(|&&cell| cell == Some(Mark::X)).count();
+    let os = grid.cells().filter(|&&cell| cell == Some(Mark::O)).count();
+
+    if xs > os + 1 || os > xs + 1 {
+        Err(NotationError::WrongMarkCounts { xs, os })
+    } else {
+        Ok((xs, os))
+    }
+}
+
+fn has_won(grid: &Grid, m: Mark) -> bool {
+    !referee::completed_lines(grid, m).is_empty()
+}
+
+/// Returns `true` if there's a mark that completed every one of the lines of `m`.
+fn could_be_won_in_one_move(grid: &Grid, m: Mark) -> bool {
+    let lines = referee::completed_lines(grid, m);
+
+    lines[0].iter().any(|pos| lines.iter().all(|line| line.contains(pos)))
+}
+
+fn with_last_mark(mut grid: Grid, last: Option<Mark>) -> Grid {
+    grid.set_last_mark(last);
+    grid
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::referee::{ Outcome, Rules };
+
+    #[test]
+    fn round_trips() {
+        for s in &["... x", "x.o/.x./o.. o", "xxx/oo./... x", "xox/xoo/oxx x", "..../..../.x../.... 3 o", "x../.../... o"] {
+            let game = s.parse::<Game>().unwrap();
+
+            assert_eq!(game.to_string(), *s);
+            assert!(s.starts_with(&game.grid().to_string()));
+        }
+
+        for s in &["...", "... 2", "x.o/.x./o..", "...../...../...../..... 3", ".../.../.../..."] {
+            assert_eq!(s.parse::<Grid>().unwrap().to_string(), *s);
+        }
+    }
+
+    #[test]
+    fn the_last_mark_of_a_grid() {
+        assert_eq!("xx./oo./x..".parse::<Grid>().unwrap().last_mark(), Some(Mark::X));
+        assert_eq!(".../.../...".parse::<Grid>().unwrap().last_mark(), None);
+        assert_eq!("x../.o./...".parse::<Grid>().unwrap().last_mark(), None);
+
+        // O started and completed the middle row with the last mark
+        let grid = "xx./ooo/x..".parse::<Grid>().unwrap();
+
+        assert_eq!(grid.last_mark(), Some(Mark::O));
+        assert_eq!(referee::evaluate(&grid, Rules::default()), Some(Outcome::Win(Mark::O)));
+    }
+
+    #[test]
+    fn a_single_row_or_column_needs_a_line_across_it() {
+        assert_eq!("...".parse::<Grid>().unwrap().shape(), Shape::new(1, 3, 3));
+        assert_eq!("x/o/. x".parse::<Game>().unwrap().grid().shape(), Shape::new(3, 1, 3));
+
+        // A single mark isn't a line
+        assert!("x.. o".parse::<Game>().unwrap().is_playing());
+    }
+
+    #[test]
+    fn finished_games() {
+        let game = "xxx/oo./... x".parse::<Game>().unwrap();
+
+        assert_eq!(game.outcome(), Some(Outcome::Win(Mark::X)));
+        assert_eq!(game.winning_lines(), vec![vec![(0, 0), (0, 1), (0, 2)]]);
+
+        let game = "xox/xoo/oxx x".parse::<Game>().unwrap();
+
+        assert_eq!(game.outcome(), Some(Outcome::Draw));
+    }
+
+    #[test]
+    fn the_position_can_be_played_on() {
+        let mut game = "x.o/.x./o.. o".parse::<Game>().unwrap();
+
+        game.play((2, 2)).unwrap();
+        game.play((1, 0)).unwrap();
+
+        assert_eq!(game.to_string(), "x.o/xx./o.o o");
+        assert_eq!(game.undo(), Some(((1, 0), Mark::X)));
+        assert_eq!(game.undo(), Some(((2, 2), Mark::O)));
+        assert_eq!(game.undo(), None);
+    }
+
+    #[test]
+    fn malformed_notation() {
+        assert_eq!("".parse::<Game>().err(), Some(NotationError::MissingCells));
+        assert_eq!("...".parse::<Game>().err(), Some(NotationError::MissingTurn));
+        assert_eq!("... 1 x o".parse::<Game>().err(), Some(NotationError::UnexpectedInput("o".to_owned())));
+        assert_eq!("x-o/.../... x".parse::<Game>().err(), Some(NotationError::InvalidCell('-')));
+        assert_eq!("xo/.../... x".parse::<Game>().err(), Some(NotationError::UnevenRows));
+        assert_eq!("/ x".parse::<Game>().err(), Some(NotationError::UnevenRows));
+        assert_eq!("... 4 x".parse::<Game>().err(), Some(NotationError::InvalidK("4".to_owned())));
+        assert_eq!("... k x".parse::<Game>().err(), Some(NotationError::InvalidK("k".to_owned())));
+        assert_eq!("... y".parse::<Game>().err(), Some(NotationError::InvalidTurn("y".to_owned())));
+
+        let too_large = vec![".".repeat(16); 16].join("/");
+
+        assert_eq!(format!("{} x", too_large).parse::<Game>().err(), Some(NotationError::TooLarge));
+    }
+
+    #[test]
+    fn impossible_positions() {
+        assert_eq!("xxx/.../... o".parse::<Game>().err(), Some(NotationError::WrongMarkCounts { xs: 3, os: 0 }));
+        assert_eq!("xxx/ooo/... x".parse::<Game>().err(), Some(NotationError::BothWon));
+        assert_eq!("xxx/ooo/...".parse::<Grid>().err(), Some(NotationError::BothWon));
+
+        // O has to be the one to move after X made the extra mark...
+        assert_eq!("x../.../... x".parse::<Game>().err(), Some(NotationError::WrongTurn(Mark::X)));
+
+        // ...and it's X's turn once X has won
+        assert_eq!("xxx/oo./... o".parse::<Game>().err(), Some(NotationError::WrongTurn(Mark::O)));
+
+        // No single move completes two parallel rows
+        assert_eq!("xxx./xxx./oo.o/o.oo 3 x".parse::<Game>().err(), Some(NotationError::ImpossibleWin(Mark::X)));
+    }
+}
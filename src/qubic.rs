@@ -0,0 +1,384 @@
+use std::sync::OnceLock;
+
+use crate::game::{ self, MoveOutcome, PlayError };
+use crate::grid::Cell;
+use crate::mark::Mark;
+use crate::referee::Outcome;
+
+/// The location of a cell within the cube of a [`QubicGame`].
+///
+/// The coordinates are the 0-based `layer`, `row` and `column`.
+///
+/// [`QubicGame`]: ./struct.QubicGame.html
+pub type QubicPosition = (usize, usize, usize);
+
+/// The number of cells along each edge of the cube.
+pub(crate) const SIZE: usize = 4;
+
+/// The game logic for Qubic, i.e. Tic-tac-toe on a 4x4x4 cube.
+///
+/// A player wins by marking 4 cells in a line, which can run along a row, a column or a pillar
+/// through the layers, or diagonally across a layer, across a vertical slice or through the
+/// middle of the cube. There are 76 such lines. The game is drawn once every cell is marked.
+///
+/// # Examples
+///
+/// ```
+/// use xsos::{ Mark, MoveOutcome, Outcome, PlayError, QubicGame };
+///
+/// # fn main() -> Result<(), PlayError> {
+/// let mut game = QubicGame::start(Mark::X);
+///
+/// // X plays down through the layers, along the cube's long diagonal
+/// for i in 0..3 {
+///     game.play((i, i, i))?;
+///     game.play((i, 0, 3))?;
+/// }
+///
+/// assert_eq!(game.play((3, 3, 3))?, MoveOutcome::GameOver(Outcome::Win(Mark::X)));
+/// assert_eq!(game.winning_lines(), vec![[(0, 0, 0), (1, 1, 1), (2, 2, 2), (3, 3, 3)]]);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct QubicGame {
+    xs: u64,
+    os: u64,
+    turn: Mark,
+    outcome: Option<Outcome>,
+    history: Vec<(QubicPosition, Mark)>
+}
+
+impl QubicGame {
+    /// Start a new game and let `first` play first.
+    pub fn start(first: Mark) -> Self {
+        Self { xs: 0, os: 0, turn: first, outcome: None, history: Vec::new() }
+    }
+
+    /// Clears the cube for a new game, which the winner of this one starts. After a draw it's
+    /// started by whoever didn't fill the last cell.
+    pub fn restart(&mut self) {
+        *self = Self::start(game::first_turn(self.turn, self.outcome));
+    }
+
+    /// Marks the cell at the given [`QubicPosition`] with the [`Mark`] given by `self.turn()`,
+    /// unless it's game over.
+    ///
+    /// It returns:
+    ///
+    /// - `Err(PlayError::GameOver)`, if this `QubicGame` is over.
+    /// - `Err(PlayError::OutOfBounds)`, if `p` is out of bounds.
+    /// - `Err(PlayError::AlreadyMarked)`, if the cell at `p` is already marked.
+    /// - `Ok(MoveOutcome::GameOver(outcome))`, if the move ended this `QubicGame`.
+    /// - `Ok(MoveOutcome::InPlay)`, otherwise.
+    ///
+    /// [`Mark`]: ./enum.Mark.html
+    /// [`QubicPosition`]: ./type.QubicPosition.html
+    pub fn play(&mut self, p: QubicPosition) -> Result<MoveOutcome, PlayError> {
+        if self.is_game_over() {
+            Err(PlayError::GameOver)
+        } else if !in_bounds(p) {
+            Err(PlayError::OutOfBounds)
+        } else if self.cell_at(p).is_some() {
+            Err(PlayError::AlreadyMarked)
+        } else {
+            let bits = self.bits(self.turn) | to_bit(p);
+
+            match self.turn {
+                Mark::X => self.xs = bits,
+                Mark::O => self.os = bits
+            }
+
+            self.history.push((p, self.turn));
+
+            if is_win(bits) {
+                self.outcome = Some(Outcome::Win(self.turn));
+            } else if (self.xs | self.os).count_ones() as usize == SIZE * SIZE * SIZE {
+                self.outcome = Some(Outcome::Draw);
+            }
+
+            self.turn = game::next_turn(self.turn, self.outcome);
+
+            match self.outcome {
+                Some(outcome) => Ok(MoveOutcome::GameOver(outcome)),
+                None => Ok(MoveOutcome::InPlay)
+            }
+        }
+    }
+
+    /// Takes back the last move, if any, and returns it along with the [`Mark`] that played it.
+    ///
+    /// [`Mark`]: ./enum.Mark.html
+    pub fn undo(&mut self) -> Option<(QubicPosition, Mark)> {
+        let (p, m) = self.history.pop()?;
+
+        self.xs &= !to_bit(p);
+        self.os &= !to_bit(p);
+        self.turn = m;
+        self.outcome = None;
+
+        Some((p, m))
+    }
+
+    /// Returns the [`Cell`] at the given [`QubicPosition`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `p` is out of bounds.
+    ///
+    /// [`Cell`]: ./type.Cell.html
+    /// [`QubicPosition`]: ./type.QubicPosition.html
+    pub fn cell_at(&self, p: QubicPosition) -> Cell {
+        assert!(in_bounds(p), "{:?} is out of bounds", p);
+
+        let bit = to_bit(p);
+
+        if self.xs & bit != 0 {
+            Some(Mark::X)
+        } else if self.os & bit != 0 {
+            Some(Mark::O)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the positions of the unmarked cells, in order of layer, row and then column.
+    /// Once the game is over there are none.
+    pub fn legal_moves(&self) -> Vec<QubicPosition> {
+        if self.is_game_over() {
+            return Vec::new();
+        }
+
+        ones(!(self.xs | self.os)).map(to_position).collect()
+    }
+
+    /// Returns the positions of the lines that won this `QubicGame`, if it was won. Otherwise it
+    /// returns an empty `Vec`.
+    pub fn winning_lines(&self) -> Vec<[QubicPosition; 4]> {
+        match self.outcome {
+            Some(Outcome::Win(winner)) => {
+                let bits = self.bits(winner);
+
+                lines()
+                    .iter()
+                    .filter(|&&line| bits & line == line)
+                    .map(|&line| {
+                        let mut positions = ones(line).map(to_position);
+
+                        [(); 4].map(|_| positions.next().unwrap())
+                    })
+                    .collect()
+            },
+            _ => Vec::new()
+        }
+    }
+
+    /// Returns the moves played so far, in the order they were played.
+    pub fn history(&self) -> &[(QubicPosition, Mark)] {
+        &self.history
+    }
+
+    /// Returns `true` if this `QubicGame` is in play.
+    pub fn is_playing(&self) -> bool {
+        self.outcome.is_none()
+    }
+
+    /// Returns `true` if this `QubicGame` is over.
+    pub fn is_game_over(&self) -> bool {
+        !self.is_playing()
+    }
+
+    /// Returns the answer to the question "Who's turn is it to play?".
+    pub fn turn(&self) -> Mark {
+        self.turn
+    }
+
+    /// Returns a reason for why this `QubicGame` is over, if it is over. Otherwise it returns `None`.
+    pub fn outcome(&self) -> Option<Outcome> {
+        self.outcome
+    }
+
+    /// Returns the bitboard of the cells marked with the given `Mark`, where the bit of a cell
+    /// is given by `to_bit`.
+    pub(crate) fn bits(&self, m: Mark) -> u64 {
+        match m {
+            Mark::X => self.xs,
+            Mark::O => self.os
+        }
+    }
+}
+
+fn in_bounds((l, r, c): QubicPosition) -> bool {
+    l < SIZE && r < SIZE && c < SIZE
+}
+
+/// Returns the bitboard with only the bit of the cell at the given position set.
+pub(crate) fn to_bit((l, r, c): QubicPosition) -> u64 {
+    1 << (l * SIZE * SIZE + r * SIZE + c)
+}
+
+pub(crate) fn to_position(i: usize) -> QubicPosition {
+    (i / (SIZE * SIZE), i / SIZE % SIZE, i % SIZE)
+}
+
+/// Returns the indices of the set bits of the bitboard, in increasing order.
+pub(crate) fn ones(bits: u64) -> impl Iterator<Item = usize> {
+    (0..SIZE * SIZE * SIZE).filter(move |&i| bits & (1 << i) != 0)
+}
+
+/// Returns `true` if the bitboard has every cell of some line set.
+pub(crate) fn is_win(bits: u64) -> bool {
+    lines().iter().any(|&line| line & !bits == 0)
+}
+
+/// Returns the bitboards of the 76 lines of the cube.
+pub(crate) fn lines() -> &'static [u64] {
+    static LINES: OnceLock<Vec<u64>> = OnceLock::new();
+
+    LINES.get_or_init(|| {
+        let size = SIZE as isize;
+        let mut lines = Vec::new();
+
+        for dl in -1..=1 {
+            for dr in -1..=1 {
+                for dc in -1..=1 {
+                    // A direction and its opposite give the same lines, so only the directions
+                    // whose first non-zero step is positive are followed
+                    if (dl, dr, dc) <= (0, 0, 0) {
+                        continue;
+                    }
+
+                    for start in 0..SIZE * SIZE * SIZE {
+                        let (l, r, c) = to_position(start);
+                        let (l, r, c) = (l as isize, r as isize, c as isize);
+                        let end = (l + (size - 1) * dl, r + (size - 1) * dr, c + (size - 1) * dc);
+
+                        if [end.0, end.1, end.2].iter().all(|i| (0..size).contains(i)) {
+                            let line = (0..size)
+                                .map(|i| to_bit(((l + i * dl) as usize, (r + i * dr) as usize, (c + i * dc) as usize)))
+                                .fold(0, |line, bit| line | bit);
+
+                            lines.push(line);
+                        }
+                    }
+                }
+            }
+        }
+
+        lines
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_cube_has_76_lines() {
+        let lines = lines();
+
+        assert_eq!(lines.len(), 76);
+        assert!(lines.iter().all(|line| line.count_ones() == 4));
+
+        // Which of the layer, row and column coordinates stay the same along each line
+        let fixed = |line: u64| {
+            let positions = ones(line).map(to_position).collect::<Vec<_>>();
+            let (l, r, c) = positions[0];
+
+            (
+                positions.iter().all(|p| p.0 == l),
+                positions.iter().all(|p| p.1 == r),
+                positions.iter().all(|p| p.2 == c)
+            )
+        };
+        let count = |kinds: &[(bool, bool, bool)]| lines.iter().filter(|&&line| kinds.contains(&fixed(line))).count();
+
+        // Rows, columns and diagonals on each of the 4 layers
+        assert_eq!(count(&[(true, true, false), (true, false, true), (true, false, false)]), 40);
+
+        // Pillars through the layers
+        assert_eq!(count(&[(false, true, true)]), 16);
+
+        // Diagonals across the vertical slices
+        assert_eq!(count(&[(false, true, false), (false, false, true)]), 16);
+
+        // Diagonals through the middle of the cube
+        assert_eq!(count(&[(false, false, false)]), 4);
+    }
+
+    #[test]
+    fn a_line_along_a_pillar_wins() {
+        let mut game = QubicGame::start(Mark::O);
+
+        for l in 0..3 {
+            game.play((l, 2, 1)).unwrap();
+            game.play((l, 0, 0)).unwrap();
+        }
+
+        assert!(game.is_playing());
+        assert_eq!(game.play((3, 2, 1)), Ok(MoveOutcome::GameOver(Outcome::Win(Mark::O))));
+        assert_eq!(game.winning_lines(), vec![[(0, 2, 1), (1, 2, 1), (2, 2, 1), (3, 2, 1)]]);
+        assert_eq!(game.turn(), Mark::O);
+        assert_eq!(game.play((3, 3, 3)), Err(PlayError::GameOver));
+    }
+
+    #[test]
+    fn a_full_cube_without_a_line_is_a_draw() {
+        let mut game = QubicGame::start(Mark::X);
+
+        // The layers of a full cube, row by row, where neither player has a line
+        let cube = "xxoxoxxoxoooxoox xxooooxxoxooxxxo ooxoxxoxoxxxoxoo ooxooxoxxxxooxxo".replace(' ', "");
+        let cells = |m: char| {
+            cube.chars().enumerate().filter(move |&(_, c)| c == m).map(|(i, _)| (i / 16, i / 4 % 4, i % 4))
+        };
+
+        for (x, o) in cells('x').zip(cells('o')) {
+            game.play(x).unwrap();
+            game.play(o).unwrap();
+        }
+
+        assert_eq!(game.outcome(), Some(Outcome::Draw));
+        assert_eq!(game.turn(), Mark::O);
+
+        // After a draw the other player goes first
+        game.restart();
+
+        assert_eq!(game.turn(), Mark::X);
+    }
+
+    #[test]
+    fn invalid_moves() {
+        let mut game = QubicGame::start(Mark::X);
+
+        assert_eq!(game.play((4, 0, 0)), Err(PlayError::OutOfBounds));
+        assert_eq!(game.play((0, 0, 0)), Ok(MoveOutcome::InPlay));
+        assert_eq!(game.play((0, 0, 0)), Err(PlayError::AlreadyMarked));
+        assert_eq!(game.turn(), Mark::O);
+        assert_eq!(game.legal_moves().len(), 63);
+    }
+
+    #[test]
+    fn undo_and_restart() {
+        let mut game = QubicGame::start(Mark::X);
+
+        for c in 0..3 {
+            game.play((1, 1, c)).unwrap();
+            game.play((2, 2, c)).unwrap();
+        }
+
+        game.play((1, 1, 3)).unwrap();
+
+        assert_eq!(game.outcome(), Some(Outcome::Win(Mark::X)));
+        assert_eq!(game.undo(), Some(((1, 1, 3), Mark::X)));
+        assert!(game.is_playing());
+        assert_eq!(game.cell_at((1, 1, 3)), None);
+        assert_eq!(game.turn(), Mark::X);
+
+        game.play((1, 1, 3)).unwrap();
+        game.restart();
+
+        assert_eq!(game.turn(), Mark::X);
+        assert!(game.history().is_empty());
+        assert_eq!(game.legal_moves().len(), 64);
+    }
+}
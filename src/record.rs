@@ -0,0 +1,574 @@
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+use std::time::{ SystemTime, UNIX_EPOCH };
+
+use crate::game::{ Game, PlayError };
+use crate::grid::{ Position, Shape };
+use crate::mark::Mark;
+use crate::notation::NotationError;
+use crate::referee::{ DrawRule, Outcome, Variant };
+
+/// A record of a [`Game`], along with who played it and when.
+///
+/// It's written in a text format that's modelled on [PGN]. A few headers, one per line, are
+/// followed by a blank line and then the moves, one per line, each with its number and its
+/// position in the same 1-based `r c` form that the CLI uses:
+///
+/// ```text
+/// [X "Human"]
+/// [O "Computer"]
+/// [Date "2020.06.30"]
+/// [First "x"]
+/// [Result "o"]
+///
+/// 1. 1 1
+/// 2. 2 2
+/// ```
+///
+/// The `First` header gives the player that moved first and the `Result` header is one of
+/// `x`, `o`, `draw` or `*` for a game that's still in play. A game that doesn't start from an
+/// empty 3x3 grid also has a `Position` header with the starting position in the notation
+/// used by `Game::from_str`, one that doesn't use `DrawRule::FullGrid` has a `Draw` header
+/// that's one of `full`, `blocked` or `perfect` and one that's played in `Variant::Misere` has
+/// a `Variant` header that's one of `standard` or `misere`.
+///
+/// As in PGN, a quote or a backslash in a header value is escaped with a backslash. A line break
+/// is written as `\n` so that every header stays on its own line.
+///
+/// # Examples
+///
+/// ```
+/// use xsos::{ Game, Mark, Record };
+///
+/// let mut game = Game::start(Mark::X);
+///
+/// game.play((1, 1)).unwrap();
+/// game.play((0, 0)).unwrap();
+///
+/// let record = Record::new(game).with_players("Alice", "Bob").with_date("2020.06.30");
+/// let text = record.to_string();
+///
+/// assert_eq!(text, "\
+/// [X \"Alice\"]
+/// [O \"Bob\"]
+/// [Date \"2020.06.30\"]
+/// [First \"x\"]
+/// [Result \"*\"]
+///
+/// 1. 2 2
+/// 2. 1 1
+/// ");
+///
+/// let game = text.parse::<Record>().unwrap().into_game();
+///
+/// assert_eq!(game.turn(), Mark::X);
+/// assert_eq!(game.moves_played(), 2);
+/// ```
+///
+/// [`Game`]: ./struct.Game.html
+/// [PGN]: https://en.wikipedia.org/wiki/Portable_Game_Notation
+#[derive(Clone)]
+pub struct Record {
+    x: String,
+    o: String,
+    date: String,
+    game: Game
+}
+
+/// The possible errors that can occur when parsing a [`Record`].
+///
+/// Lines are numbered from 1.
+///
+/// [`Record`]: ./struct.Record.html
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum RecordError {
+    /// A line in the headers isn't of the form `[Name "value"]`.
+    MalformedHeader { line: usize },
+
+    /// A header has a value that doesn't make sense for it.
+    InvalidHeader { line: usize, name: String, value: String },
+
+    /// The `Position` header isn't a valid position.
+    InvalidPosition { line: usize, error: NotationError },
+
+    /// A move isn't of the form `n. r c`.
+    MalformedMove { line: usize, number: usize },
+
+    /// A move can't be played.
+    IllegalMove { line: usize, number: usize, position: Position, error: PlayError },
+
+    /// The `Result` header doesn't match how the moves ended the game.
+    WrongResult { expected: String, actual: String }
+}
+
+impl fmt::Display for RecordError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MalformedHeader { line } =>
+                write!(f, "line {}: expected a header of the form [Name \"value\"]", line),
+            Self::InvalidHeader { line, name, value } =>
+                write!(f, "line {}: \"{}\" is not a valid {} header", line, value, name),
+            Self::InvalidPosition { line, error } =>
+                write!(f, "line {}: invalid position, {}", line, error),
+            Self::MalformedMove { line, number } =>
+                write!(f, "line {}: expected move {} in the format \"{}. r c\"", line, number, number),
+            Self::IllegalMove { line, number, position: (r, c), error } =>
+                write!(f, "line {}: move {} at {} {} is illegal, {}", line, number, r + 1, c + 1, error),
+            Self::WrongResult { expected, actual } =>
+                write!(f, "the result should be \"{}\" but the moves end with \"{}\"", expected, actual)
+        }
+    }
+}
+
+impl Error for RecordError {}
+
+impl Record {
+    /// Creates a new `Record` of the given `Game`, played today by unknown players.
+    pub fn new(game: Game) -> Self {
+        Self { x: String::from("?"), o: String::from("?"), date: today(), game }
+    }
+
+    /// Sets the names of the players.
+    pub fn with_players(mut self, x: &str, o: &str) -> Self {
+        self.x = x.to_owned();
+        self.o = o.to_owned();
+        self
+    }
+
+    /// Sets the date the game was played, in the form `YYYY.MM.DD`.
+    pub fn with_date(mut self, date: &str) -> Self {
+        self.date = date.to_owned();
+        self
+    }
+
+    /// Returns the name of the player who played X.
+    pub fn x(&self) -> &str {
+        &self.x
+    }
+
+    /// Returns the name of the player who played O.
+    pub fn o(&self) -> &str {
+        &self.o
+    }
+
+    /// Returns the date the game was played.
+    pub fn date(&self) -> &str {
+        &self.date
+    }
+
+    /// Returns the recorded `Game`.
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    /// Returns the recorded `Game`, so that it can be played on.
+    pub fn into_game(self) -> Game {
+        self.game
+    }
+}
+
+impl fmt::Display for Record {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let start = start_of(&self.game);
+
+        writeln!(f, "[X \"{}\"]", escape(&self.x))?;
+        writeln!(f, "[O \"{}\"]", escape(&self.o))?;
+        writeln!(f, "[Date \"{}\"]", escape(&self.date))?;
+        writeln!(f, "[First \"{}\"]", start.turn())?;
+
+        if start.grid().cells().any(Option::is_some) || start.grid().shape() != Shape::default() {
+            writeln!(f, "[Position \"{}\"]", start)?;
+        }
+
+        if self.game.draw_rule() != DrawRule::FullGrid {
+            writeln!(f, "[Draw \"{}\"]", format_draw_rule(self.game.draw_rule()))?;
+        }
+
+        if self.game.variant() != Variant::Standard {
+            writeln!(f, "[Variant \"{}\"]", format_variant(self.game.variant()))?;
+        }
+
+        writeln!(f, "[Result \"{}\"]", format_result(self.game.outcome()))?;
+        writeln!(f)?;
+
+        for (i, &((r, c), _)) in self.game.history().iter().enumerate() {
+            writeln!(f, "{}. {} {}", i + 1, r + 1, c + 1)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for Record {
+    type Err = RecordError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut lines = s.lines().enumerate().map(|(i, line)| (i + 1, line.trim()));
+        let mut record = Self {
+            x: String::from("?"),
+            o: String::from("?"),
+            date: String::from("????.??.??"),
+            game: Game::start(Mark::X)
+        };
+        let mut first = None;
+        let mut position = None;
+        let mut draw = DrawRule::FullGrid;
+        let mut variant = Variant::Standard;
+        let mut result = None;
+
+        for (n, line) in lines.by_ref() {
+            if line.is_empty() {
+                break;
+            }
+
+            let (name, value) = parse_header(line).ok_or(RecordError::MalformedHeader { line: n })?;
+            let invalid = || RecordError::InvalidHeader { line: n, name: name.to_owned(), value: value.clone() };
+
+            match name {
+                "X" => record.x = value,
+                "O" => record.o = value,
+                "Date" => record.date = value,
+                "First" => first = Some((n, parse_mark(&value).ok_or_else(invalid)?)),
+                "Position" => position = Some(value.parse::<Game>().map_err(|error| RecordError::InvalidPosition { line: n, error })?),
+                "Draw" => draw = parse_draw_rule(&value).ok_or_else(invalid)?,
+                "Variant" => variant = parse_variant(&value).ok_or_else(invalid)?,
+                "Result" => result = Some(value),
+                // Like PGN, headers that aren't understood are ignored
+                _ => ()
+            }
+        }
+
+        let mut game = match (position, first) {
+            (Some(game), Some((n, first))) if game.turn() != first => {
+                return Err(RecordError::InvalidHeader { line: n, name: String::from("First"), value: first.to_string() });
+            },
+            (Some(game), _) => game,
+            (None, first) => Game::start(first.map_or(Mark::X, |(_, first)| first))
+        }.with_draw_rule(draw).with_variant(variant);
+
+        let mut number = 0;
+
+        for (n, line) in lines.filter(|(_, line)| !line.is_empty()) {
+            number += 1;
+
+            let position = parse_move(line, number).ok_or(RecordError::MalformedMove { line: n, number })?;
+
+            if let Err(error) = game.play(position) {
+                return Err(RecordError::IllegalMove { line: n, number, position, error });
+            }
+        }
+
+        let actual = format_result(game.outcome());
+
+        match result {
+            Some(expected) if expected != actual => Err(RecordError::WrongResult { expected, actual: actual.to_owned() }),
+            _ => {
+                record.game = game;
+                Ok(record)
+            }
+        }
+    }
+}
+
+/// Returns the game as it was before any of its moves were played.
+fn start_of(game: &Game) -> Game {
+    let mut start = game.clone();
+
+    while start.undo().is_some() {}
+
+    start
+}
+
+fn parse_header(line: &str) -> Option<(&str, String)> {
+    let inner = line.strip_prefix('[')?.strip_suffix(']')?;
+    let (name, value) = inner.split_once(' ')?;
+    let value = value.trim().strip_prefix('"')?.strip_suffix('"')?;
+
+    Some((name, unescape(value)?))
+}
+
+/// Escapes a header value so that it stays on one line and between its quotes. As in PGN, a
+/// backslash or a quote is escaped with a backslash, and so is a line break, as `\n`.
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+
+    for c in value.chars() {
+        match c {
+            '\\' | '"' => {
+                escaped.push('\\');
+                escaped.push(c);
+            },
+            '\n' => escaped.push_str("\\n"),
+            _ => escaped.push(c)
+        }
+    }
+
+    escaped
+}
+
+/// Undoes [`escape`], or returns `None` if a backslash isn't followed by one of `\\`, `"` or `n`.
+///
+/// [`escape`]: ./fn.escape.html
+fn unescape(value: &str) -> Option<String> {
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+
+    while let Some(c) = chars.next() {
+        unescaped.push(match c {
+            '\\' => match chars.next()? {
+                'n' => '\n',
+                c @ ('\\' | '"') => c,
+                _ => return None
+            },
+            _ => c
+        });
+    }
+
+    Some(unescaped)
+}
+
+fn parse_move(line: &str, number: usize) -> Option<Position> {
+    let parts = line.split_ascii_whitespace().collect::<Vec<_>>();
+    let parts = match &parts[..] {
+        [label, rest @ ..] if label.strip_suffix('.')?.parse::<usize>().ok()? == number => rest,
+        _ => return None
+    };
+
+    match parts {
+        &[r, c] => match (r.parse::<usize>(), c.parse::<usize>()) {
+            (Ok(r), Ok(c)) if r > 0 && c > 0 => Some((r - 1, c - 1)),
+            _ => None
+        },
+        _ => None
+    }
+}
+
+fn parse_mark(s: &str) -> Option<Mark> {
+    match s {
+        "x" => Some(Mark::X),
+        "o" => Some(Mark::O),
+        _ => None
+    }
+}
+
+fn parse_draw_rule(s: &str) -> Option<DrawRule> {
+    match s {
+        "full" => Some(DrawRule::FullGrid),
+        "blocked" => Some(DrawRule::Blocked),
+        "perfect" => Some(DrawRule::PerfectPlay),
+        _ => None
+    }
+}
+
+fn format_draw_rule(draw: DrawRule) -> &'static str {
+    match draw {
+        DrawRule::FullGrid => "full",
+        DrawRule::Blocked => "blocked",
+        DrawRule::PerfectPlay => "perfect"
+    }
+}
+
+fn parse_variant(s: &str) -> Option<Variant> {
+    match s {
+        "standard" => Some(Variant::Standard),
+        "misere" => Some(Variant::Misere),
+        _ => None
+    }
+}
+
+fn format_variant(variant: Variant) -> &'static str {
+    match variant {
+        Variant::Standard => "standard",
+        Variant::Misere => "misere"
+    }
+}
+
+fn format_result(outcome: Option<Outcome>) -> &'static str {
+    match outcome {
+        Some(Outcome::Win(Mark::X)) => "x",
+        Some(Outcome::Win(Mark::O)) => "o",
+        Some(Outcome::Draw) => "draw",
+        None => "*"
+    }
+}
+
+/// Returns today's date, in UTC, in the form `YYYY.MM.DD`.
+fn today() -> String {
+    let secs = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+    let (y, m, d) = civil_from_days((secs / 86_400) as i64);
+
+    format!("{:04}.{:02}.{:02}", y, m, d)
+}
+
+/// Converts a number of days since 1970-01-01 into a (year, month, day) in the proleptic
+/// Gregorian calendar.
+///
+/// See http://howardhinnant.github.io/date_algorithms.html#civil_from_days.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let y = yoe + era * 400 + if m <= 2 { 1 } else { 0 };
+
+    (y, m, d)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn play(game: &mut Game, positions: &[Position]) {
+        for &pos in positions {
+            game.play(pos).unwrap();
+        }
+    }
+
+    #[test]
+    fn round_trips() {
+        let mut game = Game::start(Mark::O).with_draw_rule(DrawRule::Blocked);
+
+        play(&mut game, &[(1, 1), (0, 0), (2, 2), (0, 2), (0, 1), (2, 1), (1, 0)]);
+
+        let record = Record::new(game).with_players("Alice", "Bob").with_date("2020.06.30");
+        let text = record.to_string();
+        let parsed = text.parse::<Record>().unwrap();
+
+        assert_eq!(parsed.x(), "Alice");
+        assert_eq!(parsed.o(), "Bob");
+        assert_eq!(parsed.date(), "2020.06.30");
+        assert_eq!(parsed.game().history(), record.game().history());
+        assert_eq!(parsed.game().outcome(), record.game().outcome());
+        assert_eq!(parsed.to_string(), text);
+        assert!(text.contains("[Draw \"blocked\"]\n"));
+    }
+
+    #[test]
+    fn a_misere_game() {
+        let mut game = Game::start(Mark::X).with_variant(Variant::Misere);
+
+        play(&mut game, &[(0, 0), (1, 0), (0, 1), (1, 1), (0, 2)]);
+
+        let text = Record::new(game).to_string();
+
+        assert!(text.contains("[Variant \"misere\"]\n[Result \"o\"]\n"));
+        assert_eq!(text.parse::<Record>().unwrap().game().outcome(), Some(Outcome::Win(Mark::O)));
+
+        // Without the variant X would have won
+        assert!(text.replace("[Variant \"misere\"]\n", "").parse::<Record>().is_err());
+    }
+
+    #[test]
+    fn names_are_escaped() {
+        let record = Record::new(Game::start(Mark::X)).with_players("\"The\" [Kid]", "C:\\Bob\nJr");
+        let text = record.to_string();
+
+        assert!(text.starts_with("[X \"\\\"The\\\" [Kid]\"]\n[O \"C:\\\\Bob\\nJr\"]\n"));
+
+        let parsed = text.parse::<Record>().unwrap();
+
+        assert_eq!(parsed.x(), "\"The\" [Kid]");
+        assert_eq!(parsed.o(), "C:\\Bob\nJr");
+
+        assert_eq!("[X \"\\t\"]\n".parse::<Record>().err(), Some(RecordError::MalformedHeader { line: 1 }));
+    }
+
+    #[test]
+    fn a_game_that_starts_from_a_position() {
+        let mut game = "x.o/.x./o.. o".parse::<Game>().unwrap();
+
+        play(&mut game, &[(2, 2), (1, 0)]);
+
+        let text = Record::new(game).with_date("2020.06.30").to_string();
+
+        assert_eq!(text, "\
+[X \"?\"]
+[O \"?\"]
+[Date \"2020.06.30\"]
+[First \"o\"]
+[Position \"x.o/.x./o.. o\"]
+[Result \"*\"]
+
+1. 3 3
+2. 2 1
+");
+
+        let game = text.parse::<Record>().unwrap().into_game();
+
+        assert_eq!(game.to_string(), "x.o/xx./o.o o");
+
+        let game = Record::new(Game::start_with_shape(Mark::X, Shape::new(4, 4, 3))).to_string();
+
+        assert!(game.contains("[Position \"..../..../..../.... 3 x\"]\n"));
+    }
+
+    #[test]
+    fn a_position_that_is_drawn_by_the_draw_rule() {
+        let text = "[Position \"xox/xoo/ox. x\"]\n[Draw \"blocked\"]\n[Result \"draw\"]\n";
+        let mut game = text.parse::<Record>().unwrap().into_game();
+
+        assert_eq!(game.outcome(), Some(Outcome::Draw));
+        assert_eq!(game.play((2, 2)), Err(PlayError::GameOver));
+    }
+
+    #[test]
+    fn a_misere_position_that_is_drawn_with_perfect_play() {
+        // Drawn with perfect play under the standard rules, but not under misère
+        let text = "[Position \".x./.o./... x\"]\n[Draw \"perfect\"]\n[Variant \"misere\"]\n[Result \"*\"]\n";
+
+        assert!(text.parse::<Record>().unwrap().game().is_playing());
+
+        // And the other way around
+        let text = "[Position \"x.o/.../... x\"]\n[Draw \"perfect\"]\n[Variant \"misere\"]\n[Result \"draw\"]\n";
+
+        assert_eq!(text.parse::<Record>().unwrap().game().outcome(), Some(Outcome::Draw));
+    }
+
+    #[test]
+    fn errors_point_to_the_offending_line() {
+        assert_eq!(
+            "[X \"a\"]\nX \"b\"\n".parse::<Record>().err(),
+            Some(RecordError::MalformedHeader { line: 2 })
+        );
+        assert_eq!(
+            "[First \"y\"]\n".parse::<Record>().err(),
+            Some(RecordError::InvalidHeader { line: 1, name: String::from("First"), value: String::from("y") })
+        );
+        assert_eq!(
+            "[Position \"xx./.../... o\"]\n".parse::<Record>().err(),
+            Some(RecordError::InvalidPosition { line: 1, error: NotationError::WrongMarkCounts { xs: 2, os: 0 } })
+        );
+        assert_eq!(
+            "[First \"x\"]\n\n1. 2 2\n2. 1\n".parse::<Record>().err(),
+            Some(RecordError::MalformedMove { line: 4, number: 2 })
+        );
+        assert_eq!(
+            "[First \"x\"]\n\n1. 2 2\n3. 1 1\n".parse::<Record>().err(),
+            Some(RecordError::MalformedMove { line: 4, number: 2 })
+        );
+
+        let error = "[First \"x\"]\n\n1. 2 2\n2. 1 1\n3. 2 2\n".parse::<Record>().err().unwrap();
+
+        assert_eq!(error, RecordError::IllegalMove { line: 5, number: 3, position: (1, 1), error: PlayError::AlreadyMarked });
+        assert_eq!(error.to_string(), "line 5: move 3 at 2 2 is illegal, that position is already taken");
+
+        assert_eq!(
+            "[Result \"x\"]\n\n1. 2 2\n".parse::<Record>().err(),
+            Some(RecordError::WrongResult { expected: String::from("x"), actual: String::from("*") })
+        );
+    }
+
+    #[test]
+    fn dates() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(11_016), (2000, 2, 29));
+        assert_eq!(civil_from_days(18_443), (2020, 6, 30));
+        assert_eq!(civil_from_days(-1), (1969, 12, 31));
+        assert_eq!(today().len(), 10);
+    }
+}
@@ -1,55 +1,264 @@
-use crate::grid::Grid;
+use crate::bits::{ self, Bits };
+use crate::grid::{ Grid, Position, Shape };
+use crate::mark::Mark;
 
-/// A `Win` or `Draw`.
+/// A `Win`, along with the `Mark` that won, or a `Draw`.
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(rename_all = "lowercase"))]
 pub enum Outcome {
-    Win,
+    Win(Mark),
     Draw
 }
 
-/// Determines the `Outcome`, if any, of a given `Grid`.
-pub fn evaluate(grid: &Grid) -> Option<Outcome> {
+/// When a game that hasn't been won is declared a draw.
+///
+/// # Examples
+///
+/// ```
+/// use xsos::{ DrawRule, Game, Mark, Outcome };
+///
+/// let mut game = Game::start(Mark::X).with_draw_rule(DrawRule::Blocked);
+///
+/// for &pos in &[(0, 0), (0, 1), (0, 2), (1, 1), (1, 0), (1, 2), (2, 1)] {
+///     game.play(pos).unwrap();
+/// }
+///
+/// //   0   1   2
+/// // 0 x | o | x
+/// //  ---+---+---
+/// // 1 x | o | o
+/// //  ---+---+---
+/// // 2   | x |
+///
+/// // X can still complete the left column
+/// assert!(game.is_playing());
+///
+/// game.play((2, 0)).unwrap();
+///
+/// // Every line now has both an X and an O
+/// assert_eq!(game.outcome(), Some(Outcome::Draw));
+/// ```
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(rename_all = "snake_case"))]
+pub enum DrawRule {
+    /// A draw is declared once every cell is marked. This is the classic rule.
+    #[default]
+    FullGrid,
+
+    /// A draw is declared as soon as every line contains both an X and an O,
+    /// since then nobody can win.
+    Blocked,
+
+    /// A draw is declared as soon as neither player can force a win, i.e. when the
+    /// game would be drawn with perfect play from both players.
+    ///
+    /// Deciding this takes a game tree search after every move. Since no opening move
+    /// on the 3x3 grid loses, it ends every 3x3 game right after the first move.
+    ///
+    /// The search has its own transposition table for each thread, apart from the one shared by
+    /// `ai::moves` and friends, so a game never waits on a search made elsewhere.
+    ///
+    /// The search can only finish on grids that `ai::can_solve`, so on larger grids this rule
+    /// works like `Blocked`.
+    PerfectPlay
+}
+
+/// Whether completing a line wins or loses the game.
+///
+/// # Examples
+///
+/// ```
+/// use xsos::{ Game, Mark, Outcome, Variant };
+///
+/// let mut game = Game::start(Mark::X).with_variant(Variant::Misere);
+///
+/// for &pos in &[(0, 0), (1, 0), (0, 1), (1, 1), (0, 2)] {
+///     game.play(pos).unwrap();
+/// }
+///
+/// // X completed the top row, so O wins
+/// assert_eq!(game.outcome(), Some(Outcome::Win(Mark::O)));
+/// ```
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(rename_all = "snake_case"))]
+pub enum Variant {
+    /// Completing a line wins. This is the classic game.
+    #[default]
+    Standard,
+
+    /// Completing a line loses, so each player tries to make the other one complete a line.
+    /// It's also known as reverse Tic-tac-toe.
+    Misere
+}
+
+/// The rules that the referee enforces.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Default)]
+pub struct Rules {
+    pub draw: DrawRule,
+    pub variant: Variant
+}
+
+/// Determines the `Outcome`, if any, of a given `Grid` under the given `Rules`.
+///
+/// The `DrawRule::PerfectPlay` rule needs a search, which is left to the caller, so
+/// it's treated as `DrawRule::Blocked` here.
+pub fn evaluate(grid: &Grid, rules: Rules) -> Option<Outcome> {
     if is_win(grid) {
-        Some(Outcome::Win)
-    } else if is_draw(grid) {
+        grid.last_mark().map(|m| match rules.variant {
+            Variant::Standard => Outcome::Win(m),
+            Variant::Misere => Outcome::Win(m.swap())
+        })
+    } else if is_draw(grid, rules.draw) {
         Some(Outcome::Draw)
     } else {
         None
     }
 }
 
-fn is_win(grid: &Grid) -> bool {
-    let cells = grid.cells().collect::<Vec<_>>();
-    let c = grid.last_mark();
+/// Returns `true` if the last `Mark` to be marked completed a line.
+pub fn is_win(grid: &Grid) -> bool {
+    match grid.last_mark() {
+        Some(m) => has_line(grid.bits(m), grid.shape()),
+        None => false
+    }
+}
+
+fn is_draw(grid: &Grid, rule: DrawRule) -> bool {
+    let xs = grid.bits(Mark::X);
+    let os = grid.bits(Mark::O);
+    let shape = grid.shape();
+
+    match rule {
+        DrawRule::FullGrid => (xs | os).count() == shape.ncells(),
+        DrawRule::Blocked | DrawRule::PerfectPlay => {
+            let cells = board(shape);
+
+            !has_line(cells & !os, shape) && !has_line(cells & !xs, shape)
+        }
+    }
+}
+
+/// Returns the bitboard with every cell of a grid of the given `Shape` set.
+fn board(shape: Shape) -> Bits {
+    let mut bits = Bits::default();
+
+    for r in 0..shape.rows() {
+        for c in 0..shape.columns() {
+            bits.set(shape.to_bit((r, c)));
+        }
+    }
+
+    bits
+}
+
+/// Returns the positions of every line of `k` or more cells in a row that are marked with the given `Mark`.
+///
+/// Overlapping lines in the same direction, as when 4 in a row is made and 3 are needed, count as one line.
+pub fn completed_lines(grid: &Grid, m: Mark) -> Vec<Vec<Position>> {
+    let shape = grid.shape();
+    let stride = shape.stride() as isize;
+    let mut lines = Vec::new();
+
+    for &(dr, dc) in &DIRECTIONS {
+        let shift = (dr * stride + dc) as usize;
+        let starts = runs(grid.bits(m), shape, shift);
+        let to_pos = |i: usize| (i / shape.stride(), i % shape.stride());
+
+        // A line starts at each start that doesn't follow on from another one
+        for i in starts.ones().filter(|&i| i < shift || !starts.get(i - shift)) {
+            let mut last = i;
+
+            while last + shift < bits::CAPACITY && starts.get(last + shift) {
+                last += shift;
+            }
+
+            let len = (last - i) / shift + shape.k();
+            let (r, c) = to_pos(i);
+            let line = (0..len as isize)
+                .map(|j| ((r as isize + j * dr) as usize, (c as isize + j * dc) as usize))
+                .collect();
+
+            lines.push(line);
+        }
+    }
+
+    lines
+}
+
+/// The row and column steps between adjacent cells in each of the directions a line can take.
+const DIRECTIONS: [(isize, isize); 4] = [(0, 1), (1, 0), (1, 1), (1, -1)];
+
+/// A horizontal, vertical or diagonal run of `k` positions.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Line {
+    start: (isize, isize),
+    step: (isize, isize),
+    len: isize
+}
+
+impl Line {
+    /// Returns the positions on this line, starting from its first one.
+    pub(crate) fn positions(self) -> impl Iterator<Item = Position> {
+        let Line { start: (r, c), step: (dr, dc), len } = self;
+
+        (0..len).map(move |i| ((r + i * dr) as usize, (c + i * dc) as usize))
+    }
+}
+
+/// Returns an iterator over all the lines of `k` positions that fit within a grid of the given `Shape`.
+///
+/// For the 3x3 grid these are the 3 rows, the 3 columns and the 2 diagonals.
+pub(crate) fn lines(shape: Shape) -> impl Iterator<Item = Line> {
+    let rows = shape.rows() as isize;
+    let columns = shape.columns() as isize;
+    let len = shape.k() as isize;
 
-    c.is_some() && ARRANGEMENTS.iter().any(|&(i, j, k)| (cells[i], cells[j], cells[k]) == (&c, &c, &c))
+    DIRECTIONS.iter().flat_map(move |&(dr, dc)| {
+        (0..rows).flat_map(move |r| {
+            (0..columns).filter_map(move |c| {
+                let (er, ec) = (r + (len - 1) * dr, c + (len - 1) * dc);
+
+                if er >= 0 && er < rows && ec >= 0 && ec < columns {
+                    Some(Line { start: (r, c), step: (dr, dc), len })
+                } else {
+                    None
+                }
+            })
+        })
+    })
 }
 
-fn is_draw(grid: &Grid) -> bool {
-    grid.cells().all(Option::is_some)
+/// Returns `true` if the bitboard has `k` bits in a row in any direction.
+fn has_line(bits: Bits, shape: Shape) -> bool {
+    let stride = shape.stride();
+
+    [1, stride, stride + 1, stride - 1].iter().any(|&shift| !runs(bits, shape, shift).is_empty())
 }
 
-const ARRANGEMENTS: [(usize, usize, usize); 8] = [
-    (0, 1, 2),
-    (3, 4, 5),
-    (6, 7, 8),
-    (0, 3, 6),
-    (1, 4, 7),
-    (2, 5, 8),
-    (0, 4, 8),
-    (2, 4, 6)
-];
+/// Returns the bits that start a run of `k` bits, each `shift` apart, in the bitboard.
+///
+/// Shifting a bitboard by the distance between adjacent cells in some direction and
+/// and-ing it with itself leaves the cells that start a run of 2. Doing it `k - 1`
+/// times leaves the cells that start a run of `k`.
+fn runs(bits: Bits, shape: Shape, shift: usize) -> Bits {
+    let mut runs = bits;
+
+    for _ in 1..shape.k() {
+        runs = runs & (runs >> shift);
+    }
+
+    runs
+}
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::mark::Mark;
 
     #[test]
     fn evaluate_on_an_empty_grid_returns_none() {
         let grid = Grid::new();
 
-        assert!(evaluate(&grid).is_none());
+        assert!(evaluate(&grid, Rules::default()).is_none());
     }
 
     #[test]
@@ -62,7 +271,22 @@ mod tests {
         grid.mark((1, 1), Mark::O);
         grid.mark((0, 2), Mark::X);
 
-        assert_eq!(evaluate(&grid), Some(Outcome::Win));
+        assert_eq!(evaluate(&grid, Rules::default()), Some(Outcome::Win(Mark::X)));
+    }
+
+    #[test]
+    fn evaluate_gives_the_win_to_the_other_player_in_misere() {
+        let mut grid = Grid::new();
+
+        grid.mark((0, 0), Mark::X);
+        grid.mark((1, 0), Mark::O);
+        grid.mark((0, 1), Mark::X);
+        grid.mark((1, 1), Mark::O);
+        grid.mark((0, 2), Mark::X);
+
+        let rules = Rules { variant: Variant::Misere, ..Rules::default() };
+
+        assert_eq!(evaluate(&grid, rules), Some(Outcome::Win(Mark::O)));
     }
 
     #[test]
@@ -79,6 +303,144 @@ mod tests {
         grid.mark((2, 2), Mark::O);
         grid.mark((2, 1), Mark::X);
 
-        assert_eq!(evaluate(&grid), Some(Outcome::Draw));
+        assert_eq!(evaluate(&grid, Rules::default()), Some(Outcome::Draw));
+    }
+
+    #[test]
+    fn a_line_cannot_wrap_around_to_the_next_row() {
+        let mut grid = Grid::new();
+
+        grid.mark((0, 2), Mark::X);
+        grid.mark((1, 0), Mark::X);
+        grid.mark((1, 1), Mark::X);
+
+        assert!(evaluate(&grid, Rules::default()).is_none());
+
+        let mut grid = Grid::new();
+
+        grid.mark((0, 0), Mark::O);
+        grid.mark((1, 2), Mark::O);
+        grid.mark((2, 1), Mark::O);
+
+        assert!(evaluate(&grid, Rules::default()).is_none());
+    }
+
+    #[test]
+    fn has_line_agrees_with_lines() {
+        let shape = Shape::new(4, 5, 3);
+
+        for line in lines(shape) {
+            let mut grid = Grid::with_shape(shape);
+
+            for p in line.positions() {
+                assert!(!has_line(grid.bits(Mark::X), shape));
+                grid.mark(p, Mark::X);
+            }
+
+            assert!(has_line(grid.bits(Mark::X), shape));
+        }
+    }
+
+    #[test]
+    fn blocked_draw_with_empty_cells() {
+        let mut grid = Grid::new();
+
+        grid.mark((0, 0), Mark::X);
+        grid.mark((0, 1), Mark::O);
+        grid.mark((0, 2), Mark::X);
+        grid.mark((1, 1), Mark::O);
+        grid.mark((1, 0), Mark::X);
+        grid.mark((1, 2), Mark::O);
+        grid.mark((2, 1), Mark::X);
+
+        // X can still complete the left column
+        assert!(evaluate(&grid, Rules { draw: DrawRule::Blocked, ..Rules::default() }).is_none());
+
+        grid.mark((2, 0), Mark::O);
+
+        assert!(evaluate(&grid, Rules::default()).is_none());
+        assert_eq!(evaluate(&grid, Rules { draw: DrawRule::Blocked, ..Rules::default() }), Some(Outcome::Draw));
+    }
+
+    #[test]
+    fn the_3x3_grid_has_8_lines() {
+        assert_eq!(lines(Shape::default()).count(), 8);
+    }
+
+    #[test]
+    fn the_15x15_grid_with_5_in_a_row_has_572_lines() {
+        assert_eq!(lines(Shape::new(15, 15, 5)).count(), 572);
+    }
+
+    #[test]
+    fn evaluate_detects_a_diagonal_win_on_a_larger_grid() {
+        let mut grid = Grid::with_shape(Shape::new(4, 5, 4));
+
+        grid.mark((0, 4), Mark::O);
+        grid.mark((1, 3), Mark::O);
+        grid.mark((2, 2), Mark::O);
+
+        assert!(evaluate(&grid, Rules::default()).is_none());
+
+        grid.mark((3, 1), Mark::O);
+
+        assert_eq!(evaluate(&grid, Rules::default()), Some(Outcome::Win(Mark::O)));
+    }
+
+    #[test]
+    fn evaluate_requires_k_in_a_row() {
+        let mut grid = Grid::with_shape(Shape::new(4, 4, 4));
+
+        grid.mark((1, 0), Mark::X);
+        grid.mark((1, 1), Mark::X);
+        grid.mark((1, 2), Mark::X);
+
+        assert!(evaluate(&grid, Rules::default()).is_none());
+
+        grid.mark((1, 3), Mark::X);
+
+        assert_eq!(evaluate(&grid, Rules::default()), Some(Outcome::Win(Mark::X)));
+    }
+
+    #[test]
+    fn completed_lines_finds_every_line() {
+        let mut grid = Grid::new();
+
+        grid.mark((0, 1), Mark::X);
+        grid.mark((1, 1), Mark::O);
+        grid.mark((0, 2), Mark::X);
+        grid.mark((2, 2), Mark::O);
+        grid.mark((1, 0), Mark::X);
+        grid.mark((1, 2), Mark::O);
+        grid.mark((2, 0), Mark::X);
+        grid.mark((2, 1), Mark::O);
+
+        assert!(completed_lines(&grid, Mark::X).is_empty());
+
+        // Completes the top row and the left column at once
+        grid.mark((0, 0), Mark::X);
+
+        assert_eq!(completed_lines(&grid, Mark::X), vec![
+            vec![(0, 0), (0, 1), (0, 2)],
+            vec![(0, 0), (1, 0), (2, 0)]
+        ]);
+    }
+
+    #[test]
+    fn completed_lines_merges_overlapping_lines() {
+        let mut grid = Grid::with_shape(Shape::new(5, 5, 3));
+
+        for i in 0..5 {
+            grid.mark((i, 4 - i), Mark::O);
+        }
+
+        grid.mark((4, 0), Mark::O);
+        grid.mark((4, 1), Mark::O);
+        grid.mark((4, 2), Mark::O);
+
+        assert_eq!(completed_lines(&grid, Mark::O), vec![
+            vec![(4, 0), (4, 1), (4, 2)],
+            vec![(0, 4), (1, 3), (2, 2), (3, 1), (4, 0)]
+        ]);
     }
 }
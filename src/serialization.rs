@@ -0,0 +1,422 @@
+use serde::de::{ self, Deserializer };
+use serde::ser::Serializer;
+use serde::{ Deserialize, Serialize };
+
+use crate::game::Game;
+use crate::grid::{ Grid, Position };
+use crate::mark::Mark;
+use crate::referee::{ DrawRule, Outcome, Variant };
+
+#[derive(Serialize, Deserialize)]
+struct GridRepr {
+    rows: usize,
+    columns: usize,
+    k: usize,
+    cells: Vec<String>
+}
+
+#[derive(Serialize, Deserialize)]
+struct GameRepr {
+    rows: usize,
+    columns: usize,
+    k: usize,
+    cells: Vec<String>,
+    turn: Mark,
+    state: StateRepr,
+    #[serde(default)]
+    draw: DrawRule,
+    #[serde(default)]
+    variant: Variant,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    history: Vec<MoveRepr>
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum StateRepr {
+    Play,
+    Draw,
+    Win(Mark)
+}
+
+#[derive(Serialize, Deserialize)]
+struct MoveRepr {
+    position: Position,
+    mark: Mark
+}
+
+/// Serializes a `Grid` as an object with its shape and its rows of cells, written as in its
+/// notation:
+///
+/// ```json
+/// { "rows": 3, "columns": 3, "k": 3, "cells": ["x.o", ".x.", "..."] }
+/// ```
+///
+/// # Examples
+///
+/// ```
+/// use xsos::Grid;
+///
+/// let grid = "x.o/.x./...".parse::<Grid>().unwrap();
+///
+/// assert_eq!(
+///     serde_json::to_string(&grid).unwrap(),
+///     r#"{"rows":3,"columns":3,"k":3,"cells":["x.o",".x.","..."]}"#
+/// );
+/// ```
+impl Serialize for Grid {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let shape = self.shape();
+
+        GridRepr {
+            rows: shape.rows(),
+            columns: shape.columns(),
+            k: shape.k(),
+            cells: format_rows(self)
+        }
+        .serialize(serializer)
+    }
+}
+
+/// Deserializes a `Grid` from the object that it's serialized as.
+///
+/// It's rejected unless it could be reached in some game, just as when it's parsed from its
+/// notation.
+///
+/// # Examples
+///
+/// ```
+/// use xsos::Grid;
+///
+/// let json = r#"{"rows":3,"columns":3,"k":3,"cells":["xx.","...","..."]}"#;
+///
+/// assert!(serde_json::from_str::<Grid>(json).is_err());
+/// ```
+impl<'de> Deserialize<'de> for Grid {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let GridRepr { rows, columns, k, cells } = GridRepr::deserialize(deserializer)?;
+
+        parse_grid(rows, columns, k, &cells)
+    }
+}
+
+/// Serializes a `Game` as an object with the fields of its [`Grid`], the turn, the state, the
+/// [`DrawRule`], the [`Variant`] and the moves that were played, if any:
+///
+/// ```json
+/// {
+///   "rows": 3,
+///   "columns": 3,
+///   "k": 3,
+///   "cells": ["x..", ".o.", "..."],
+///   "turn": "x",
+///   "state": "play",
+///   "draw": "full_grid",
+///   "variant": "standard",
+///   "history": [
+///     { "position": [0, 0], "mark": "x" },
+///     { "position": [1, 1], "mark": "o" }
+///   ]
+/// }
+/// ```
+///
+/// The turn is written as for [`Game::turn`], so once the game is over it's the player who made
+/// the final move. The state is one of `"play"`, `"draw"` or `{ "win": "x" }`, the draw rule is
+/// one of `"full_grid"`, `"blocked"` or `"perfect_play"` and the variant is one of `"standard"` or
+/// `"misere"`. The history is left out when it's empty.
+///
+/// # Examples
+///
+/// ```
+/// use xsos::{ Game, Mark };
+///
+/// let mut game = Game::start(Mark::X);
+///
+/// game.play((0, 0)).unwrap();
+///
+/// assert_eq!(
+///     serde_json::to_string(&game).unwrap(),
+///     concat!(
+///         r#"{"rows":3,"columns":3,"k":3,"cells":["x..","...","..."],"turn":"o","state":"play","#,
+///         r#""draw":"full_grid","variant":"standard","history":[{"position":[0,0],"mark":"x"}]}"#
+///     )
+/// );
+/// ```
+///
+/// [`DrawRule`]: ./enum.DrawRule.html
+/// [`Game::turn`]: ./struct.Game.html#method.turn
+/// [`Grid`]: ./struct.Grid.html
+/// [`Variant`]: ./enum.Variant.html
+impl Serialize for Game {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let grid = self.grid();
+        let shape = grid.shape();
+
+        GameRepr {
+            rows: shape.rows(),
+            columns: shape.columns(),
+            k: shape.k(),
+            cells: format_rows(grid),
+            turn: self.turn(),
+            state: to_state(self.outcome()),
+            draw: self.draw_rule(),
+            variant: self.variant(),
+            history: self.history().iter().map(|&(position, mark)| MoveRepr { position, mark }).collect()
+        }
+        .serialize(serializer)
+    }
+}
+
+/// Deserializes a `Game` from the object that it's serialized as.
+///
+/// The draw rule, the variant and the history are optional. The moves in the history are taken back from the
+/// cells and the position that's left has to be one that could be reached in some game. The
+/// moves are then replayed and the turn and the state have to agree with where they lead.
+///
+/// # Examples
+///
+/// ```
+/// use xsos::{ Game, Mark };
+///
+/// let json = r#"{"rows":3,"columns":3,"k":3,"cells":["x..",".o.","..."],"turn":"x","state":"play"}"#;
+/// let game = serde_json::from_str::<Game>(json).unwrap();
+///
+/// assert_eq!(game.turn(), Mark::X);
+/// assert!(game.history().is_empty());
+///
+/// // Nobody has won
+/// let json = r#"{"rows":3,"columns":3,"k":3,"cells":["x..",".o.","..."],"turn":"o","state":{"win":"o"}}"#;
+///
+/// assert!(serde_json::from_str::<Game>(json).is_err());
+/// ```
+impl<'de> Deserialize<'de> for Game {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let repr = GameRepr::deserialize(deserializer)?;
+        let mut start = parse_grid::<D::Error>(repr.rows, repr.columns, repr.k, &repr.cells)?;
+
+        for &MoveRepr { position, mark } in &repr.history {
+            if !start.shape().in_bounds(position) || start.cell_at(position) != Some(mark) {
+                return Err(de::Error::custom(format!("{:?} isn't marked {} like the history says", position, mark)));
+            }
+
+            start.unmark(position, None);
+        }
+
+        let first = repr.history.first().map_or(repr.turn, |m| m.mark);
+        let notation = format!("{} {} {}", format_rows(&start).join("/"), repr.k, first);
+        let mut game = notation.parse::<Game>().map_err(de::Error::custom)?.with_draw_rule(repr.draw).with_variant(repr.variant);
+
+        for &MoveRepr { position, mark } in &repr.history {
+            if game.turn() != mark {
+                return Err(de::Error::custom(format!("{:?} was played out of turn by {}", position, mark)));
+            }
+
+            game.play(position)
+                .map_err(|e| de::Error::custom(format!("{:?} can't be played, {}", position, e)))?;
+        }
+
+        if game.turn() != repr.turn {
+            return Err(de::Error::custom(format!("it can't be {}'s turn", repr.turn)));
+        }
+
+        if to_state(game.outcome()) != repr.state {
+            return Err(de::Error::custom(format!("the state should be {:?}", to_state(game.outcome()))));
+        }
+
+        Ok(game)
+    }
+}
+
+fn parse_grid<E: de::Error>(rows: usize, columns: usize, k: usize, cells: &[String]) -> Result<Grid, E> {
+    if rows == 0 || columns == 0 || cells.len() != rows || cells.iter().any(|row| row.chars().count() != columns) {
+        return Err(E::custom(format!("expected {} rows of {} cells", rows, columns)));
+    }
+
+    let grid = format!("{} {}", cells.join("/"), k).parse::<Grid>().map_err(E::custom)?;
+    let shape = grid.shape();
+
+    // A row could have smuggled in a separator
+    if (shape.rows(), shape.columns()) != (rows, columns) {
+        return Err(E::custom(format!("expected {} rows of {} cells", rows, columns)));
+    }
+
+    Ok(grid)
+}
+
+fn format_rows(grid: &Grid) -> Vec<String> {
+    let shape = grid.shape();
+
+    (0..shape.rows())
+        .map(|r| {
+            (0..shape.columns())
+                .map(|c| match grid.cell_at((r, c)) {
+                    Some(Mark::X) => 'x',
+                    Some(Mark::O) => 'o',
+                    None => '.'
+                })
+                .collect()
+        })
+        .collect()
+}
+
+fn to_state(outcome: Option<Outcome>) -> StateRepr {
+    match outcome {
+        None => StateRepr::Play,
+        Some(Outcome::Draw) => StateRepr::Draw,
+        Some(Outcome::Win(winner)) => StateRepr::Win(winner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::PlayError;
+    use crate::grid::Shape;
+
+    fn round_trip(game: &Game) -> Game {
+        serde_json::from_str(&serde_json::to_string(game).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn the_shapes_of_the_simple_types() {
+        assert_eq!(serde_json::to_string(&Mark::O).unwrap(), r#""o""#);
+        assert_eq!(serde_json::to_string(&Outcome::Win(Mark::X)).unwrap(), r#"{"win":"x"}"#);
+        assert_eq!(serde_json::to_string(&Outcome::Draw).unwrap(), r#""draw""#);
+        assert_eq!(serde_json::to_string(&PlayError::AlreadyMarked).unwrap(), r#""already_marked""#);
+        assert_eq!(serde_json::to_string(&DrawRule::PerfectPlay).unwrap(), r#""perfect_play""#);
+
+        assert_eq!(serde_json::from_str::<Mark>(r#""x""#).unwrap(), Mark::X);
+        assert_eq!(serde_json::from_str::<Outcome>(r#"{"win":"o"}"#).unwrap(), Outcome::Win(Mark::O));
+        assert_eq!(serde_json::from_str::<PlayError>(r#""game_over""#).unwrap(), PlayError::GameOver);
+    }
+
+    #[test]
+    fn round_trips() {
+        let mut game = Game::start_with_shape(Mark::O, Shape::new(4, 5, 3)).with_draw_rule(DrawRule::Blocked);
+
+        for &pos in &[(0, 0), (1, 1), (0, 1), (2, 2)] {
+            game.play(pos).unwrap();
+        }
+
+        let copy = round_trip(&game);
+
+        assert_eq!(copy.to_string(), game.to_string());
+        assert_eq!(copy.history(), game.history());
+        assert_eq!(copy.draw_rule(), DrawRule::Blocked);
+
+        game.play((0, 2)).unwrap();
+
+        let copy = round_trip(&game);
+
+        assert_eq!(copy.outcome(), Some(Outcome::Win(Mark::O)));
+        assert_eq!(copy.winning_lines(), game.winning_lines());
+
+        let grid = "x.o/.x./... 2".parse::<Grid>().unwrap();
+        let copy = serde_json::from_str::<Grid>(&serde_json::to_string(&grid).unwrap()).unwrap();
+
+        assert_eq!(copy.to_string(), grid.to_string());
+        assert_eq!(copy.last_mark(), grid.last_mark());
+    }
+
+    #[test]
+    fn a_game_that_starts_from_a_position() {
+        let mut game = "x.o/.x./... o".parse::<Game>().unwrap();
+
+        game.play((2, 2)).unwrap();
+        game.play((2, 0)).unwrap();
+
+        let mut copy = round_trip(&game);
+
+        assert_eq!(copy.history(), game.history());
+
+        // Only the moves in the history can be taken back
+        copy.undo();
+        copy.undo();
+
+        assert_eq!(copy.undo(), None);
+        assert_eq!(copy.to_string(), "x.o/.x./... o");
+    }
+
+    #[test]
+    fn the_state_is_decided_under_the_draw_rule() {
+        let mut game = Game::start(Mark::X).with_draw_rule(DrawRule::Blocked);
+
+        for &pos in &[(0, 0), (0, 1), (0, 2), (1, 1), (1, 0), (1, 2), (2, 1), (2, 0)] {
+            game.play(pos).unwrap();
+        }
+
+        assert_eq!(round_trip(&game).outcome(), Some(Outcome::Draw));
+
+        // Under the classic rule there's still a cell to play
+        let json = serde_json::to_string(&game).unwrap().replace("blocked", "full_grid");
+
+        assert!(serde_json::from_str::<Game>(&json).is_err());
+    }
+
+    #[test]
+    fn the_winner_is_decided_by_the_variant() {
+        let mut game = Game::start(Mark::X).with_variant(Variant::Misere);
+
+        for &pos in &[(0, 0), (1, 0), (0, 1), (1, 1), (0, 2)] {
+            game.play(pos).unwrap();
+        }
+
+        let json = serde_json::to_string(&game).unwrap();
+
+        assert!(json.contains(r#""state":{"win":"o"}"#));
+        assert_eq!(serde_json::from_str::<Game>(&json).unwrap().outcome(), Some(Outcome::Win(Mark::O)));
+
+        // In the standard game X would have won
+        assert!(serde_json::from_str::<Game>(&json.replace("misere", "standard")).is_err());
+    }
+
+    #[test]
+    fn inconsistent_games() {
+        let game = |cells: &str, turn: &str, state: &str, history: &str| {
+            let json = format!(
+                r#"{{"rows":3,"columns":3,"k":3,"cells":{},"turn":"{}","state":{}{}}}"#,
+                cells, turn, state, history
+            );
+
+            serde_json::from_str::<Game>(&json).map(|game| game.to_string()).map_err(|e| e.to_string())
+        };
+
+        assert_eq!(game(r#"["x..",".o.","..."]"#, "x", r#""play""#, ""), Ok(String::from("x../.o./... x")));
+
+        // Too many x's
+        assert!(game(r#"["xx.","...","..."]"#, "o", r#""play""#, "").is_err());
+
+        // Uneven rows
+        assert!(game(r#"["x..",".o","..."]"#, "x", r#""play""#, "").is_err());
+        assert!(game(r#"["x/.",".o.","..."]"#, "x", r#""play""#, "").is_err());
+
+        // The wrong turn or state
+        assert!(game(r#"["xx.",".o.","..."]"#, "x", r#""play""#, "").is_err());
+        assert!(game(r#"["x..",".o.","..."]"#, "x", r#""draw""#, "").is_err());
+
+        let history = |moves: &[(usize, usize, &str)]| {
+            let moves = moves
+                .iter()
+                .map(|(r, c, m)| format!(r#"{{"position":[{},{}],"mark":"{}"}}"#, r, c, m))
+                .collect::<Vec<_>>();
+
+            format!(r#","history":[{}]"#, moves.join(","))
+        };
+
+        assert!(game(r#"["x..",".o.","..."]"#, "x", r#""play""#, &history(&[(0, 0, "x"), (1, 1, "o")])).is_ok());
+
+        // A move that isn't on the grid
+        assert!(game(r#"["x..",".o.","..."]"#, "x", r#""play""#, &history(&[(0, 0, "x"), (2, 2, "o")])).is_err());
+
+        // The same move twice
+        assert!(game(r#"["x..",".o.","..."]"#, "x", r#""play""#, &history(&[(0, 0, "x"), (0, 0, "x")])).is_err());
+
+        // Out of turn
+        assert!(game(r#"["xx.",".o.","..."]"#, "o", r#""play""#, &history(&[(0, 0, "x"), (0, 1, "x")])).is_err());
+
+        // A move after the game was won
+        assert!(
+            game(r#"["xxx","oo.","..o"]"#, "o", r#"{"win":"x"}"#, &history(&[(0, 2, "x"), (2, 2, "o")])).is_err()
+        );
+    }
+}
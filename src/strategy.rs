@@ -0,0 +1,359 @@
+use rand::rngs::StdRng;
+use rand::seq::{ IteratorRandom, SliceRandom };
+use rand::{ Rng, SeedableRng };
+
+use crate::ai;
+use crate::game::Game;
+use crate::grid::Position;
+
+/// A way of choosing the moves for a player of a [`Game`].
+///
+/// # Examples
+///
+/// ```
+/// use xsos::{ FirstUnmarked, Game, Mark, Perfect, Strategy };
+///
+/// let mut game = Game::start(Mark::X);
+/// let mut players: [Box<dyn Strategy>; 2] = [Box::new(FirstUnmarked), Box::new(Perfect::new())];
+///
+/// while game.is_playing() {
+///     let pos = match game.turn() {
+///         Mark::X => players[0].choose(&game),
+///         Mark::O => players[1].choose(&game)
+///     };
+///
+///     game.play(pos).unwrap();
+/// }
+///
+/// // X only ever plays into the first empty cell, which O always punishes
+/// assert_eq!(game.outcome(), Some(xsos::Outcome::Win(Mark::O)));
+/// ```
+///
+/// [`Game`]: ./struct.Game.html
+pub trait Strategy {
+    /// Chooses a position to play at in the given `game`.
+    ///
+    /// It's only ever called while the `game` is in play and the position it returns
+    /// must be unmarked.
+    fn choose(&mut self, game: &Game) -> Position;
+}
+
+/// Plays one of the equally-best moves given by [`ai::moves`], chosen at random.
+///
+/// [`ai::moves`]: ./ai/fn.moves.html
+#[derive(Debug, Clone)]
+pub struct Perfect {
+    rng: StdRng
+}
+
+/// Plays at any unmarked position, chosen at random.
+#[derive(Debug, Clone)]
+pub struct Random {
+    rng: StdRng
+}
+
+/// Plays at the first unmarked position in row-major order.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct FirstUnmarked;
+
+/// Plays one of the equally-best moves given by [`ai::lookahead_moves`], chosen at random.
+///
+/// [`ai::lookahead_moves`]: ./ai/fn.lookahead_moves.html
+#[derive(Debug, Clone)]
+pub struct Lookahead {
+    plies: usize,
+    rng: StdRng
+}
+
+/// Plays like [`Perfect`] except that, with probability `epsilon`, it blunders by playing
+/// like [`Random`] instead.
+///
+/// [`Perfect`]: ./struct.Perfect.html
+/// [`Random`]: ./struct.Random.html
+#[derive(Debug, Clone)]
+pub struct Blundering {
+    epsilon: f64,
+    rng: StdRng
+}
+
+/// How hard it is to beat the computer.
+///
+/// # Examples
+///
+/// ```
+/// use xsos::{ Difficulty, Game, Mark };
+///
+/// let mut game = Game::start(Mark::X);
+///
+/// game.play((0, 0)).unwrap();
+/// game.play((1, 1)).unwrap();
+/// game.play((0, 1)).unwrap();
+///
+/// // Anything but easy blocks X
+/// assert_eq!(Difficulty::Medium.strategy().choose(&game), (0, 2));
+/// assert_eq!(Difficulty::Hard.strategy().choose(&game), (0, 2));
+/// ```
+#[derive(Debug, Default, PartialEq, Clone, Copy)]
+pub enum Difficulty {
+    /// Plays like [`Random`].
+    ///
+    /// [`Random`]: ./struct.Random.html
+    Easy,
+
+    /// Plays like a [`Lookahead`] of 2 plies, so it takes a win or blocks one when it can
+    /// but otherwise plays at random.
+    ///
+    /// [`Lookahead`]: ./struct.Lookahead.html
+    Medium,
+
+    /// Plays like [`Perfect`].
+    ///
+    /// [`Perfect`]: ./struct.Perfect.html
+    #[default]
+    Hard,
+
+    /// Plays like [`Blundering`] with the given `epsilon`.
+    ///
+    /// [`Blundering`]: ./struct.Blundering.html
+    Blunder(f64)
+}
+
+impl Perfect {
+    /// Creates a new `Perfect` that breaks ties between equally-best moves using a randomly seeded
+    /// random number generator.
+    pub fn new() -> Self {
+        Self { rng: StdRng::from_entropy() }
+    }
+
+    /// Seeds the random number generator so that the same moves get chosen every time.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use xsos::{ Game, Mark, Perfect, Strategy };
+    ///
+    /// let game = Game::start(Mark::X);
+    /// let mut a = Perfect::new().with_seed(7);
+    /// let mut b = Perfect::new().with_seed(7);
+    ///
+    /// for _ in 0..5 {
+    ///     assert_eq!(a.choose(&game), b.choose(&game));
+    /// }
+    /// ```
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = StdRng::seed_from_u64(seed);
+        self
+    }
+}
+
+impl Random {
+    /// Creates a new `Random` that uses a randomly seeded random number generator.
+    pub fn new() -> Self {
+        Self { rng: StdRng::from_entropy() }
+    }
+
+    /// Seeds the random number generator so that the same moves get chosen every time.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = StdRng::seed_from_u64(seed);
+        self
+    }
+}
+
+impl Lookahead {
+    /// Creates a new `Lookahead` that looks the given number of `plies` ahead.
+    pub fn new(plies: usize) -> Self {
+        Self { plies, rng: StdRng::from_entropy() }
+    }
+
+    /// Seeds the random number generator so that the same moves get chosen every time.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = StdRng::seed_from_u64(seed);
+        self
+    }
+}
+
+impl Blundering {
+    /// Creates a new `Blundering` that blunders with probability `epsilon`.
+    ///
+    /// # Panics
+    ///
+    /// If `epsilon` isn't between 0 and 1.
+    pub fn new(epsilon: f64) -> Self {
+        assert!((0.0..=1.0).contains(&epsilon), "epsilon must be between 0 and 1, got {}", epsilon);
+
+        Self { epsilon, rng: StdRng::from_entropy() }
+    }
+
+    /// Seeds the random number generator so that the same moves, and blunders, get chosen every time.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = StdRng::seed_from_u64(seed);
+        self
+    }
+}
+
+impl Difficulty {
+    /// Returns the [`Strategy`] that plays at this `Difficulty`.
+    ///
+    /// [`Strategy`]: ./trait.Strategy.html
+    pub fn strategy(self) -> Box<dyn Strategy> {
+        match self {
+            Self::Easy => Box::new(Random::new()),
+            Self::Medium => Box::new(Lookahead::new(2)),
+            Self::Hard => Box::new(Perfect::new()),
+            Self::Blunder(epsilon) => Box::new(Blundering::new(epsilon))
+        }
+    }
+
+    /// Returns the [`Strategy`] that plays at this `Difficulty`, with its random number generator
+    /// seeded by `seed`.
+    ///
+    /// [`Strategy`]: ./trait.Strategy.html
+    pub fn seeded_strategy(self, seed: u64) -> Box<dyn Strategy> {
+        match self {
+            Self::Easy => Box::new(Random::new().with_seed(seed)),
+            Self::Medium => Box::new(Lookahead::new(2).with_seed(seed)),
+            Self::Hard => Box::new(Perfect::new().with_seed(seed)),
+            Self::Blunder(epsilon) => Box::new(Blundering::new(epsilon).with_seed(seed))
+        }
+    }
+}
+
+impl Default for Perfect {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Default for Random {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Strategy for Perfect {
+    fn choose(&mut self, game: &Game) -> Position {
+        ai::random_move(game, &mut self.rng)
+    }
+}
+
+impl Strategy for Random {
+    fn choose(&mut self, game: &Game) -> Position {
+        game.grid().unmarked_positions().choose(&mut self.rng).unwrap()
+    }
+}
+
+impl Strategy for FirstUnmarked {
+    fn choose(&mut self, game: &Game) -> Position {
+        game.grid().unmarked_positions().next().unwrap()
+    }
+}
+
+impl Strategy for Lookahead {
+    fn choose(&mut self, game: &Game) -> Position {
+        *ai::lookahead_moves(game, self.plies).choose(&mut self.rng).unwrap()
+    }
+}
+
+impl Strategy for Blundering {
+    fn choose(&mut self, game: &Game) -> Position {
+        if self.rng.gen_bool(self.epsilon) {
+            game.grid().unmarked_positions().choose(&mut self.rng).unwrap()
+        } else {
+            ai::random_move(game, &mut self.rng)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mark::Mark;
+    use crate::referee::Outcome;
+
+    fn play_out(game: &mut Game, x: &mut dyn Strategy, o: &mut dyn Strategy) -> Outcome {
+        while game.is_playing() {
+            let pos = match game.turn() {
+                Mark::X => x.choose(game),
+                Mark::O => o.choose(game)
+            };
+
+            game.play(pos).unwrap();
+        }
+
+        game.outcome().unwrap()
+    }
+
+    #[test]
+    fn first_unmarked_plays_in_row_major_order() {
+        let mut game = Game::start(Mark::X);
+
+        let outcome = play_out(&mut game, &mut FirstUnmarked, &mut FirstUnmarked);
+
+        assert_eq!(outcome, Outcome::Win(Mark::X));
+        assert_eq!(game.history(), &[
+            ((0, 0), Mark::X), ((0, 1), Mark::O), ((0, 2), Mark::X),
+            ((1, 0), Mark::O), ((1, 1), Mark::X), ((1, 2), Mark::O),
+            ((2, 0), Mark::X)
+        ]);
+    }
+
+    #[test]
+    fn medium_takes_the_win_before_blocking() {
+        let mut game = Game::start(Mark::X);
+
+        game.play((0, 0)).unwrap();
+        game.play((1, 0)).unwrap();
+        game.play((0, 1)).unwrap();
+        game.play((1, 1)).unwrap();
+        game.play((2, 2)).unwrap();
+
+        // O could block at (0, 2) but winning at (1, 2) is better
+        for _ in 0..10 {
+            assert_eq!(Difficulty::Medium.strategy().choose(&game), (1, 2));
+        }
+    }
+
+    #[test]
+    fn medium_loses_to_a_fork() {
+        let mut game = Game::start(Mark::O);
+        let mut medium = Lookahead::new(2).with_seed(1);
+        let mut perfect = Perfect::new().with_seed(2);
+
+        // Medium only looks 2 plies ahead so it can't see a fork coming, and over
+        // enough games Perfect gets the chance to set one up
+        let wins = (0..50)
+            .filter(|_| {
+                game.restart();
+                play_out(&mut game, &mut medium, &mut perfect) == Outcome::Win(Mark::O)
+            })
+            .count();
+
+        assert!(wins > 0);
+    }
+
+    #[test]
+    fn never_blundering_is_perfect() {
+        for _ in 0..20 {
+            let mut game = Game::start(Mark::X);
+
+            assert_eq!(play_out(&mut game, &mut Blundering::new(0.0), &mut Perfect::new()), Outcome::Draw);
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn epsilon_is_a_probability() {
+        Blundering::new(1.5);
+    }
+
+    #[test]
+    fn perfect_never_loses_to_random() {
+        for &first in &[Mark::X, Mark::O] {
+            for _ in 0..20 {
+                let mut game = Game::start(first);
+
+                assert_ne!(play_out(&mut game, &mut Random::new(), &mut Perfect::new()), Outcome::Win(Mark::X));
+            }
+        }
+    }
+}
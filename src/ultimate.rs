@@ -0,0 +1,409 @@
+use crate::game::{ self, BoardPlayError, MoveOutcome, PlayError };
+use crate::grid::{ Grid, Position };
+use crate::mark::Mark;
+use crate::referee::{ self, Outcome, Rules };
+
+/// A move in an [`UltimateGame`], given by the position of a board on the meta-grid and the
+/// position of a cell on that board.
+///
+/// [`UltimateGame`]: ./struct.UltimateGame.html
+pub type UltimateMove = (Position, Position);
+
+/// The game logic for Ultimate Tic-tac-toe.
+///
+/// It's played on a 3x3 meta-grid of 3x3 boards. A player wins a board by completing a line on
+/// it, as in Tic-tac-toe, and wins the game by winning three boards in a line on the meta-grid.
+///
+/// The cell a player marks sends the other player to the board at the same position on the
+/// meta-grid, where they have to play next. If that board is already closed, i.e. won or full,
+/// they can play on any board that's still open instead. The game is drawn once every board is
+/// closed without anybody winning a line of boards.
+///
+/// # Examples
+///
+/// ```
+/// use xsos::{ BoardPlayError, Mark, UltimateGame };
+///
+/// # fn main() -> Result<(), BoardPlayError> {
+/// let mut game = UltimateGame::start(Mark::X);
+///
+/// // X can play anywhere to start with, so they mark the middle cell of the top left board
+/// game.play((0, 0), (1, 1))?;
+///
+/// // That sends O to the middle board
+/// assert_eq!(game.boards(), vec![(1, 1)]);
+/// assert_eq!(game.play((0, 0), (0, 0)), Err(BoardPlayError::WrongBoard));
+///
+/// game.play((1, 1), (0, 0))?;
+///
+/// // And O sends X back to the top left board
+/// assert_eq!(game.boards(), vec![(0, 0)]);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct UltimateGame {
+    boards: Vec<Grid>,
+    outcomes: [Option<Outcome>; 9],
+    meta: Grid,
+    turn: Mark,
+    next: Option<Position>,
+    outcome: Option<Outcome>,
+    history: Vec<(UltimateMove, Mark)>,
+    saved: Vec<Saved>
+}
+
+/// What a move changed that can't be worked out again when it's taken back.
+#[derive(Clone, Copy)]
+struct Saved {
+    next: Option<Position>,
+    board_last: Option<Mark>,
+    meta_last: Option<Mark>
+}
+
+impl UltimateGame {
+    /// Start a new game and let `first` play first.
+    pub fn start(first: Mark) -> Self {
+        Self {
+            boards: vec![Grid::new(); 9],
+            outcomes: [None; 9],
+            meta: Grid::new(),
+            turn: first,
+            next: None,
+            outcome: None,
+            history: Vec::new(),
+            saved: Vec::new()
+        }
+    }
+
+    /// Clears all nine boards for a new game, which can again be started on any of them. The
+    /// winner of the meta-grid plays first, or after a draw whoever didn't make the last move.
+    pub fn restart(&mut self) {
+        *self = Self::start(game::first_turn(self.turn, self.outcome));
+    }
+
+    /// Marks the `cell` on the given `board` with the [`Mark`] given by `self.turn()`, unless it's
+    /// game over.
+    ///
+    /// It returns:
+    ///
+    /// - `Err(BoardPlayError::Play(PlayError::GameOver))`, if this `UltimateGame` is over.
+    /// - `Err(BoardPlayError::Play(PlayError::OutOfBounds))`, if `board` or `cell` is out of bounds.
+    /// - `Err(BoardPlayError::WrongBoard)`, if the move can't be played on `board`.
+    /// - `Err(BoardPlayError::Play(PlayError::AlreadyMarked))`, if the `cell` is already marked.
+    /// - `Ok(MoveOutcome::GameOver(outcome))`, if the move ended this `UltimateGame`.
+    /// - `Ok(MoveOutcome::InPlay)`, otherwise.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use xsos::{ Mark, MoveOutcome, Outcome, UltimateGame };
+    ///
+    /// let mut game = UltimateGame::start(Mark::X);
+    ///
+    /// // O keeps sending X back to the top left board, which X wins along a diagonal
+    /// for &(board, cell) in &[((0, 0), (0, 2)), ((0, 2), (0, 0)), ((0, 0), (1, 1)), ((1, 1), (0, 0))] {
+    ///     game.play(board, cell).unwrap();
+    /// }
+    ///
+    /// assert_eq!(game.play((0, 0), (2, 0)), Ok(MoveOutcome::InPlay));
+    /// assert_eq!(game.board_outcome((0, 0)), Some(Outcome::Win(Mark::X)));
+    /// assert_eq!(game.meta_grid().cell_at((0, 0)), Some(Mark::X));
+    /// ```
+    ///
+    /// [`Mark`]: ./enum.Mark.html
+    pub fn play(&mut self, board: Position, cell: Position) -> Result<MoveOutcome, BoardPlayError> {
+        if self.is_game_over() {
+            Err(BoardPlayError::Play(PlayError::GameOver))
+        } else if !Grid::in_bounds(board) || !Grid::in_bounds(cell) {
+            Err(BoardPlayError::Play(PlayError::OutOfBounds))
+        } else if !self.is_open(board) {
+            Err(BoardPlayError::WrongBoard)
+        } else if self.board(board).is_marked_at(cell) {
+            Err(BoardPlayError::Play(PlayError::AlreadyMarked))
+        } else {
+            self.unchecked_play((board, cell));
+
+            match self.outcome {
+                Some(outcome) => Ok(MoveOutcome::GameOver(outcome)),
+                None => Ok(MoveOutcome::InPlay)
+            }
+        }
+    }
+
+    pub(crate) fn unchecked_play(&mut self, (board, cell): UltimateMove) {
+        let i = index(board);
+
+        self.saved.push(Saved {
+            next: self.next,
+            board_last: self.boards[i].last_mark(),
+            meta_last: self.meta.last_mark()
+        });
+        self.history.push(((board, cell), self.turn));
+
+        self.boards[i].mark(cell, self.turn);
+        self.outcomes[i] = referee::evaluate(&self.boards[i], Rules::default());
+
+        if let Some(Outcome::Win(winner)) = self.outcomes[i] {
+            self.meta.mark(board, winner);
+
+            if referee::is_win(&self.meta) {
+                self.outcome = Some(Outcome::Win(winner));
+            }
+        }
+
+        if self.outcome.is_none() && self.outcomes.iter().all(Option::is_some) {
+            self.outcome = Some(Outcome::Draw);
+        }
+
+        self.next = if self.outcomes[index(cell)].is_none() { Some(cell) } else { None };
+
+        self.turn = game::next_turn(self.turn, self.outcome);
+    }
+
+    /// Takes back the last move, if any, and returns it along with the [`Mark`] that played it.
+    ///
+    /// [`Mark`]: ./enum.Mark.html
+    pub fn undo(&mut self) -> Option<(UltimateMove, Mark)> {
+        let ((board, cell), mark) = self.history.pop()?;
+        let Saved { next, board_last, meta_last } = self.saved.pop().unwrap();
+        let i = index(board);
+
+        self.boards[i].unmark(cell, board_last);
+
+        // The board was open before the move, so if it's won now it was won by this move
+        if let Some(Outcome::Win(_)) = self.outcomes[i] {
+            self.meta.unmark(board, meta_last);
+        }
+
+        self.outcomes[i] = None;
+        self.next = next;
+        self.turn = mark;
+        self.outcome = None;
+
+        Some(((board, cell), mark))
+    }
+
+    /// Returns the positions of the boards that the next move can be played on, in row-major order.
+    ///
+    /// It's the board the last move sent the player to, if that board is still open, or else every
+    /// open board. Once the game is over there are none.
+    pub fn boards(&self) -> Vec<Position> {
+        if self.is_game_over() {
+            return Vec::new();
+        }
+
+        match self.next {
+            Some(board) => vec![board],
+            None => self.meta.unmarked_positions().filter(|&board| self.board_outcome(board).is_none()).collect()
+        }
+    }
+
+    /// Returns every move that can be played next, in row-major order of the boards and then of
+    /// the cells on each board.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use xsos::{ Mark, UltimateGame };
+    ///
+    /// let mut game = UltimateGame::start(Mark::X);
+    ///
+    /// assert_eq!(game.legal_moves().len(), 81);
+    ///
+    /// game.play((2, 2), (0, 1)).unwrap();
+    ///
+    /// assert_eq!(game.legal_moves().len(), 9);
+    /// assert_eq!(game.legal_moves()[0], ((0, 1), (0, 0)));
+    /// ```
+    pub fn legal_moves(&self) -> Vec<UltimateMove> {
+        self.boards()
+            .into_iter()
+            .flat_map(|board| self.board(board).unmarked_positions().map(move |cell| (board, cell)))
+            .collect()
+    }
+
+    fn is_open(&self, board: Position) -> bool {
+        self.board_outcome(board).is_none() && self.next.is_none_or(|next| next == board)
+    }
+
+    /// Returns the [`Grid`] of the board at the given position on the meta-grid.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `board` is out of bounds.
+    ///
+    /// [`Grid`]: ./struct.Grid.html
+    pub fn board(&self, board: Position) -> &Grid {
+        &self.boards[index(board)]
+    }
+
+    /// Returns the [`Outcome`] of the board at the given position on the meta-grid, if it's closed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `board` is out of bounds.
+    ///
+    /// [`Outcome`]: ./enum.Outcome.html
+    pub fn board_outcome(&self, board: Position) -> Option<Outcome> {
+        self.outcomes[index(board)]
+    }
+
+    /// Returns the meta-grid, where each board that's been won is marked with the [`Mark`] of its
+    /// winner. Drawn boards stay unmarked.
+    ///
+    /// [`Mark`]: ./enum.Mark.html
+    pub fn meta_grid(&self) -> &Grid {
+        &self.meta
+    }
+
+    /// Returns the moves played so far, in the order they were played.
+    pub fn history(&self) -> &[(UltimateMove, Mark)] {
+        &self.history
+    }
+
+    /// Returns `true` if this `UltimateGame` is in play.
+    pub fn is_playing(&self) -> bool {
+        self.outcome.is_none()
+    }
+
+    /// Returns `true` if this `UltimateGame` is over.
+    pub fn is_game_over(&self) -> bool {
+        !self.is_playing()
+    }
+
+    /// Returns the answer to the question "Who's turn is it to play?".
+    pub fn turn(&self) -> Mark {
+        self.turn
+    }
+
+    /// Returns a reason for why this `UltimateGame` is over, if it is over. Otherwise it returns `None`.
+    pub fn outcome(&self) -> Option<Outcome> {
+        self.outcome
+    }
+}
+
+/// The index of a board, or of a cell, on a 3x3 grid in row-major order.
+fn index((r, c): Position) -> usize {
+    assert!(r < 3 && c < 3, "{:?} is out of bounds", (r, c));
+
+    r * 3 + c
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Plays the moves, each given as a position on the 9x9 grid of cells.
+    fn play_all(game: &mut UltimateGame, moves: &[Position]) {
+        for &(r, c) in moves {
+            game.play((r / 3, c / 3), (r % 3, c % 3)).unwrap();
+        }
+    }
+
+    #[test]
+    fn the_first_move_can_be_played_anywhere() {
+        let game = UltimateGame::start(Mark::O);
+
+        assert_eq!(game.turn(), Mark::O);
+        assert_eq!(game.boards().len(), 9);
+        assert_eq!(game.legal_moves().len(), 81);
+    }
+
+    #[test]
+    fn a_move_sends_the_other_player_to_a_board() {
+        let mut game = UltimateGame::start(Mark::X);
+
+        game.play((1, 1), (0, 2)).unwrap();
+
+        assert_eq!(game.turn(), Mark::O);
+        assert_eq!(game.boards(), vec![(0, 2)]);
+        assert_eq!(game.play((1, 1), (0, 0)), Err(BoardPlayError::WrongBoard));
+        assert_eq!(game.play((0, 2), (3, 0)), Err(BoardPlayError::Play(PlayError::OutOfBounds)));
+
+        game.play((0, 2), (1, 1)).unwrap();
+
+        // Back to the middle board, where the cell X marked is taken
+        assert_eq!(game.play((1, 1), (0, 2)), Err(BoardPlayError::Play(PlayError::AlreadyMarked)));
+    }
+
+    #[test]
+    fn being_sent_to_a_closed_board_gives_a_free_choice() {
+        let mut game = UltimateGame::start(Mark::X);
+
+        // X wins the top left board down its left column
+        play_all(&mut game, &[(0, 0), (1, 1), (4, 4), (3, 3), (1, 0), (3, 0), (2, 0)]);
+
+        assert_eq!(game.board_outcome((0, 0)), Some(Outcome::Win(Mark::X)));
+
+        // O is sent to the top left board, which is closed
+        game.play((2, 0), (0, 0)).unwrap();
+
+        assert_eq!(game.turn(), Mark::X);
+        assert_eq!(game.boards().len(), 8);
+        assert!(!game.boards().contains(&(0, 0)));
+        assert_eq!(game.play((0, 0), (2, 2)), Err(BoardPlayError::WrongBoard));
+    }
+
+    #[test]
+    fn winning_three_boards_in_a_line_wins_the_game() {
+        let mut game = UltimateGame::start(Mark::X);
+
+        // X wins the top row of boards, the last of them along its diagonal
+        play_all(&mut game, &[
+            (0, 6), (0, 1), (0, 3), (0, 0), (0, 2), (0, 7), (0, 5), (0, 8), (2, 8),
+            (6, 8), (1, 7), (3, 3), (1, 1), (3, 4), (0, 4), (3, 0), (2, 0)
+        ]);
+
+        assert_eq!(game.meta_grid().cell_at((0, 0)), Some(Mark::X));
+        assert_eq!(game.meta_grid().cell_at((0, 1)), Some(Mark::X));
+        assert_eq!(game.meta_grid().cell_at((0, 2)), Some(Mark::X));
+        assert_eq!(game.outcome(), Some(Outcome::Win(Mark::X)));
+        assert_eq!(game.turn(), Mark::X);
+        assert!(game.boards().is_empty());
+        assert_eq!(game.play((2, 2), (2, 2)), Err(BoardPlayError::Play(PlayError::GameOver)));
+
+        game.restart();
+
+        assert_eq!(game.turn(), Mark::X);
+        assert!(game.history().is_empty());
+    }
+
+    #[test]
+    fn closing_every_board_without_a_line_of_boards_is_a_draw() {
+        let mut game = UltimateGame::start(Mark::X);
+
+        play_all(&mut game, &[
+            (5, 3), (8, 0), (6, 2), (1, 7), (5, 4), (6, 3), (1, 0), (3, 1), (2, 3), (7, 2), (3, 8),
+            (2, 6), (7, 0), (5, 1), (6, 4), (1, 5), (5, 8), (6, 6), (1, 1), (3, 5), (2, 8), (6, 7),
+            (0, 3), (0, 0), (1, 2), (4, 7), (5, 5), (6, 8), (0, 6), (4, 1), (3, 6), (7, 4), (2, 4),
+            (8, 5), (2, 5), (7, 1), (6, 1), (4, 8), (3, 7), (0, 8), (6, 0)
+        ]);
+
+        assert_eq!(game.outcome(), Some(Outcome::Draw));
+        assert_eq!(game.turn(), Mark::X);
+
+        // After a draw the other player goes first
+        game.restart();
+
+        assert_eq!(game.turn(), Mark::O);
+    }
+
+    #[test]
+    fn undo_takes_back_a_board_win() {
+        let mut game = UltimateGame::start(Mark::X);
+
+        play_all(&mut game, &[(0, 0), (1, 1), (4, 4), (3, 3), (1, 0), (3, 0)]);
+
+        let before = game.legal_moves();
+
+        game.play((0, 0), (2, 0)).unwrap();
+
+        assert_eq!(game.undo(), Some((((0, 0), (2, 0)), Mark::X)));
+        assert_eq!(game.board_outcome((0, 0)), None);
+        assert_eq!(game.meta_grid().cell_at((0, 0)), None);
+        assert_eq!(game.board((0, 0)).last_mark(), Some(Mark::X));
+        assert_eq!(game.turn(), Mark::X);
+        assert_eq!(game.legal_moves(), before);
+    }
+}
@@ -0,0 +1,261 @@
+use crate::game::{ self, MoveOutcome, PlayError };
+use crate::grid::{ Grid, Position, Shape };
+use crate::mark::Mark;
+use crate::referee::{ self, Outcome };
+
+/// A move in a [`WildGame`], given by the position of a cell and the [`Mark`] to put there.
+///
+/// [`Mark`]: ./enum.Mark.html
+/// [`WildGame`]: ./struct.WildGame.html
+pub type WildMove = (Position, Mark);
+
+/// The game logic for Wild Tic-tac-toe.
+///
+/// It's played on a [`Grid`] like a [`Game`], except that on each turn the player chooses which
+/// [`Mark`] to put down as well as where to put it. Whoever completes a line of either mark wins,
+/// and the game is drawn once every cell is marked.
+///
+/// The players are still called X and O, after the mark they'd play in a [`Game`], and
+/// `self.turn()` tells whose turn it is. It doesn't constrain the mark they play.
+///
+/// # Examples
+///
+/// ```
+/// use xsos::{ Mark, MoveOutcome, Outcome, PlayError, WildGame };
+///
+/// # fn main() -> Result<(), PlayError> {
+/// let mut game = WildGame::start(Mark::X);
+///
+/// // X plays an o, then O plays another o next to it
+/// game.play((0, 0), Mark::O)?;
+/// game.play((0, 1), Mark::O)?;
+///
+/// // So X completes the top row with a third o and wins
+/// assert_eq!(game.play((0, 2), Mark::O)?, MoveOutcome::GameOver(Outcome::Win(Mark::X)));
+/// assert_eq!(game.winning_lines(), vec![vec![(0, 0), (0, 1), (0, 2)]]);
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`Game`]: ./struct.Game.html
+/// [`Grid`]: ./struct.Grid.html
+/// [`Mark`]: ./enum.Mark.html
+#[derive(Clone)]
+pub struct WildGame {
+    grid: Grid,
+    turn: Mark,
+    outcome: Option<Outcome>,
+    history: Vec<(WildMove, Mark)>
+}
+
+impl WildGame {
+    /// Start a new game and let `first` play first.
+    pub fn start(first: Mark) -> Self {
+        Self::start_with_shape(first, Shape::default())
+    }
+
+    /// Start a new game on a [`Grid`] with the given [`Shape`] and let `first` play first.
+    ///
+    /// [`Grid`]: ./struct.Grid.html
+    /// [`Shape`]: ./struct.Shape.html
+    pub fn start_with_shape(first: Mark, shape: Shape) -> Self {
+        Self { grid: Grid::with_shape(shape), turn: first, outcome: None, history: Vec::new() }
+    }
+
+    /// Restart a game on an empty grid of the same shape. Whoever completed a line, with either
+    /// mark, plays first, or after a draw whoever didn't make the last move.
+    pub fn restart(&mut self) {
+        *self = Self::start_with_shape(game::first_turn(self.turn, self.outcome), self.grid.shape());
+    }
+
+    /// Marks the [`Cell`] at the given [`Position`] with the given [`Mark`], on behalf of the
+    /// player given by `self.turn()`, unless it's game over.
+    ///
+    /// It returns:
+    ///
+    /// - `Err(PlayError::GameOver)`, if this `WildGame` is over.
+    /// - `Err(PlayError::OutOfBounds)`, if `p` is out of bounds.
+    /// - `Err(PlayError::AlreadyMarked)`, if the [`Cell`] at `p` is already marked.
+    /// - `Ok(MoveOutcome::GameOver(outcome))`, if the move ended this `WildGame`.
+    /// - `Ok(MoveOutcome::InPlay)`, otherwise.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use xsos::{ Mark, MoveOutcome, PlayError, WildGame };
+    ///
+    /// let mut game = WildGame::start(Mark::X);
+    ///
+    /// assert_eq!(game.play((0, 3), Mark::X), Err(PlayError::OutOfBounds));
+    /// assert_eq!(game.play((1, 1), Mark::O), Ok(MoveOutcome::InPlay));
+    /// assert_eq!(game.play((1, 1), Mark::X), Err(PlayError::AlreadyMarked));
+    ///
+    /// // It's O's turn, whichever mark X played
+    /// assert_eq!(game.turn(), Mark::O);
+    /// ```
+    ///
+    /// [`Cell`]: ./type.Cell.html
+    /// [`Mark`]: ./enum.Mark.html
+    /// [`Position`]: ./type.Position.html
+    pub fn play(&mut self, p: Position, m: Mark) -> Result<MoveOutcome, PlayError> {
+        if self.is_game_over() {
+            Err(PlayError::GameOver)
+        } else if !self.grid.shape().in_bounds(p) {
+            Err(PlayError::OutOfBounds)
+        } else if self.grid.is_marked_at(p) {
+            Err(PlayError::AlreadyMarked)
+        } else {
+            self.grid.mark(p, m);
+            self.history.push(((p, m), self.turn));
+
+            // Only the mark that was just played can have completed a line
+            if referee::is_win(&self.grid) {
+                self.outcome = Some(Outcome::Win(self.turn));
+            } else if self.grid.unmarked_positions().next().is_none() {
+                self.outcome = Some(Outcome::Draw);
+            }
+
+            self.turn = game::next_turn(self.turn, self.outcome);
+
+            match self.outcome {
+                Some(outcome) => Ok(MoveOutcome::GameOver(outcome)),
+                None => Ok(MoveOutcome::InPlay)
+            }
+        }
+    }
+
+    /// Takes back the last move, if any, and returns it along with the player who played it.
+    pub fn undo(&mut self) -> Option<(WildMove, Mark)> {
+        let ((p, m), player) = self.history.pop()?;
+        let last = self.history.last().map(|&((_, m), _)| m);
+
+        self.grid.unmark(p, last);
+        self.turn = player;
+        self.outcome = None;
+
+        Some(((p, m), player))
+    }
+
+    /// Returns the [`Grid`] managed by this `WildGame`.
+    ///
+    /// [`Grid`]: ./struct.Grid.html
+    pub fn grid(&self) -> &Grid {
+        &self.grid
+    }
+
+    /// Returns every move that can be played next, i.e. each unmarked position in row-major
+    /// order, first with an X and then with an O. Once the game is over there are none.
+    pub fn legal_moves(&self) -> Vec<WildMove> {
+        if self.is_game_over() {
+            return Vec::new();
+        }
+
+        self.grid
+            .unmarked_positions()
+            .flat_map(|p| [(p, Mark::X), (p, Mark::O)])
+            .collect()
+    }
+
+    /// Returns the positions of the lines that won this `WildGame`, if it was won. Otherwise it
+    /// returns an empty `Vec`.
+    pub fn winning_lines(&self) -> Vec<Vec<Position>> {
+        match (self.outcome, self.grid.last_mark()) {
+            (Some(Outcome::Win(_)), Some(last)) => referee::completed_lines(&self.grid, last),
+            _ => Vec::new()
+        }
+    }
+
+    /// Returns the moves played so far, in the order they were played, along with the players
+    /// who played them.
+    pub fn history(&self) -> &[(WildMove, Mark)] {
+        &self.history
+    }
+
+    /// Returns `true` if this `WildGame` is in play.
+    pub fn is_playing(&self) -> bool {
+        self.outcome.is_none()
+    }
+
+    /// Returns `true` if this `WildGame` is over.
+    pub fn is_game_over(&self) -> bool {
+        !self.is_playing()
+    }
+
+    /// Returns the answer to the question "Who's turn is it to play?".
+    pub fn turn(&self) -> Mark {
+        self.turn
+    }
+
+    /// Returns a reason for why this `WildGame` is over, if it is over. Otherwise it returns `None`.
+    pub fn outcome(&self) -> Option<Outcome> {
+        self.outcome
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn either_player_can_complete_a_line_of_either_mark() {
+        let mut game = WildGame::start(Mark::O);
+
+        game.play((0, 0), Mark::X).unwrap();
+        game.play((1, 1), Mark::X).unwrap();
+
+        assert_eq!(game.legal_moves().len(), 14);
+        assert_eq!(game.play((2, 2), Mark::X), Ok(MoveOutcome::GameOver(Outcome::Win(Mark::O))));
+        assert_eq!(game.winning_lines(), vec![vec![(0, 0), (1, 1), (2, 2)]]);
+        assert!(game.legal_moves().is_empty());
+        assert_eq!(game.play((0, 1), Mark::O), Err(PlayError::GameOver));
+    }
+
+    #[test]
+    fn a_full_grid_without_a_line_is_a_draw() {
+        let mut game = WildGame::start(Mark::X);
+
+        // x o x
+        // x o o
+        // o x x
+        for &(p, m) in &[
+            ((0, 0), Mark::X), ((0, 1), Mark::O), ((0, 2), Mark::X),
+            ((1, 0), Mark::X), ((1, 1), Mark::O), ((1, 2), Mark::O),
+            ((2, 0), Mark::O), ((2, 1), Mark::X), ((2, 2), Mark::X)
+        ] {
+            game.play(p, m).unwrap();
+        }
+
+        assert_eq!(game.outcome(), Some(Outcome::Draw));
+        assert!(game.winning_lines().is_empty());
+
+        // After a draw the other player goes first
+        game.restart();
+
+        assert_eq!(game.turn(), Mark::O);
+    }
+
+    #[test]
+    fn undo_and_restart() {
+        let mut game = WildGame::start_with_shape(Mark::X, Shape::new(3, 4, 3));
+
+        game.play((0, 0), Mark::O).unwrap();
+        game.play((0, 1), Mark::X).unwrap();
+        game.play((1, 1), Mark::O).unwrap();
+
+        assert_eq!(game.undo(), Some((((1, 1), Mark::O), Mark::X)));
+        assert_eq!(game.turn(), Mark::X);
+        assert_eq!(game.grid().last_mark(), Some(Mark::X));
+        assert!(game.grid().is_unmarked_at((1, 1)));
+
+        game.play((1, 0), Mark::O).unwrap();
+        game.play((2, 0), Mark::O).unwrap();
+
+        assert_eq!(game.outcome(), Some(Outcome::Win(Mark::O)));
+
+        game.restart();
+
+        assert_eq!(game.turn(), Mark::O);
+        assert!(game.history().is_empty());
+        assert_eq!(game.grid().shape(), Shape::new(3, 4, 3));
+    }
+}